@@ -1,70 +1,885 @@
 use std::env;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::output::ColorChoice;
+use crate::util::parse_size;
+use rmx_lib::fs_profile::{FsKind, StrategyOverrides};
+use rmx_lib::quote::QuotingStyle;
+use rmx_lib::retry::Retry;
+use rmx_lib::template::EntryTemplate;
+use rmx_lib::throttle::RateLimiter;
+use rmx_lib::truncate::GradualTruncate;
+use rmx_lib::RemoveOptions;
 
 #[derive(Debug, Clone, Default)]
 pub struct RmxArgs {
-    pub paths: Vec<String>,
+    pub paths: Vec<PathBuf>,
     pub recursive: bool,   // -r, -R, --recursive
     pub force: bool,       // -f, --force
     pub interactive: bool, // -i, --interactive
     pub verbose: bool,     // -v, --verbose
     pub dir: bool,         // -d, --dir (remove empty directories)
-    pub quiet: bool,       // -q, --quiet (suppress output for benchmarking)
+    pub background: bool,  // --background (rename now, delete in a detached worker)
+    pub nice: bool,        // --nice (idle I/O class, lowest CPU priority)
+    pub progress: bool,    // --progress (live progress bar)
+    pub no_prescan: bool,  // --no-prescan (progress without pre-count and ETA)
+    // --max-ops-per-sec=N, --max-bytes-per-sec=SIZE
+    pub max_ops_per_sec: Option<u64>,
+    pub max_bytes_per_sec: Option<u64>,
+    // --gradual-truncate[=CHUNK], --truncate-threshold=SIZE, --truncate-pause=MS
+    pub gradual_truncate: Option<GradualTruncate>,
+    pub journal: Option<PathBuf>, // --journal=FILE
+    pub resume: Option<PathBuf>,  // --resume=FILE (targets come from the journal)
+    // -q, --quiet, --summary=none|short|full
+    pub summary: Summary,
+    // --output=text|json
+    pub output: OutputFormat,
+    pub events: Option<PathBuf>,       // --events=FILE (- for stdout)
+    pub format: Option<EntryTemplate>, // --format=TEMPLATE
+    // --quoting-style=literal|shell|shell-escape|c|escape
+    pub quoting_style: QuotingStyle,
+    // --color[=WHEN], --ascii
+    pub color: ColorChoice,
+    pub ascii: bool,
+    // --retries=N, --retry-delay=MS
+    pub retry: Option<Retry>,
+    // --fs-type, --threads, --parallel-threshold, --batch-size
+    pub strategy: StrategyOverrides,
+    // --help, --version (anywhere on the command line)
+    pub show_help: bool,
+    pub show_version: bool,
+}
+
+impl RmxArgs {
+    pub fn remove_options(&self) -> RemoveOptions {
+        RemoveOptions {
+            verbose: self.verbose,
+            force: self.force,
+            strategy: self.strategy.clone(),
+            ops_limit: self.max_ops_per_sec.map(|n| Arc::new(RateLimiter::new(n))),
+            bytes_limit: self
+                .max_bytes_per_sec
+                .map(|n| Arc::new(RateLimiter::new(n))),
+            low_priority: self.nice,
+            gradual_truncate: self.gradual_truncate,
+            progress: None,
+            cancel: None,
+            journal: None,
+            retry: self.retry,
+            one_file_system: false,
+            events: None,
+            format: self.format.clone(),
+        }
+    }
+}
+
+/// What to print once done (`--summary`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Summary {
+    /// Nothing on success, only errors
+    None,
+    /// Totals, size and time taken
+    #[default]
+    Short,
+    /// Totals followed by a line per command line argument
+    Full,
+}
+
+impl Summary {
+    pub const ALL: [Summary; 3] = [Summary::None, Summary::Short, Summary::Full];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Summary::None => "none",
+            Summary::Short => "short",
+            Summary::Full => "full",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Summary> {
+        Self::ALL.into_iter().find(|level| level.name() == name)
+    }
+}
+
+/// How the summary is printed (`--output`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// A single JSON document on stdout, see `report::print_json`
+    Json,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 2] = [OutputFormat::Text, OutputFormat::Json];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+}
+
+/// Value taken by an option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    None,
+    /// `--name=VALUE` or `--name VALUE`
+    Required(&'static str),
+    /// `--name` alone or `--name=VALUE`
+    Optional(&'static str),
+}
+
+/// A command-line option, as parsed and as listed by `--help`
+#[derive(Debug, Clone, Copy)]
+pub struct OptionSpec {
+    pub long: &'static str,
+    pub short: &'static [char],
+    pub value: Value,
+    /// Help text, one entry per line; empty for unlisted options
+    pub help: &'static [&'static str],
+}
+
+pub const fn flag(
+    long: &'static str,
+    short: &'static [char],
+    help: &'static [&'static str],
+) -> OptionSpec {
+    OptionSpec {
+        long,
+        short,
+        value: Value::None,
+        help,
+    }
 }
 
-pub fn parse_args() -> Vec<String> {
-    env::args().collect()
+pub const fn valued(long: &'static str, value: Value, help: &'static [&'static str]) -> OptionSpec {
+    OptionSpec {
+        long,
+        short: &[],
+        value,
+        help,
+    }
+}
+
+/// Every option, in the groups shown by `--help`
+pub const OPTION_GROUPS: &[&[OptionSpec]] = &[
+    &[
+        flag(
+            "--recursive",
+            &['r', 'R'],
+            &["Remove directories and their contents recursively"],
+        ),
+        flag(
+            "--force",
+            &['f'],
+            &["Ignore nonexistent files, never prompt"],
+        ),
+        flag("--interactive", &['i'], &["Prompt before every removal"]),
+        flag("--verbose", &['v'], &["Explain what is being done"]),
+        valued(
+            "--format",
+            Value::Required("TEMPLATE"),
+            &[
+                "Print TEMPLATE for each removed entry, find -printf",
+                "style: %p path, %s size, %T mtime, %y type,",
+                "%d depth, %u owner, %U uid; escapes \\n \\t \\0",
+            ],
+        ),
+        flag("--dir", &['d'], &["Remove empty directories"]),
+        flag(
+            "--quiet",
+            &['q'],
+            &["Print nothing on success (same as --summary=none)"],
+        ),
+        valued(
+            "--summary",
+            Value::Required("LEVEL"),
+            &["none, short (default) or full, with a line per argument"],
+        ),
+        valued(
+            "--output",
+            Value::Required("FORMAT"),
+            &["Summary as text (default) or json"],
+        ),
+        valued(
+            "--events",
+            Value::Required("FILE"),
+            &[
+                "Write a JSON line per removed, skipped or failed",
+                "entry to FILE (- for stdout)",
+            ],
+        ),
+    ],
+    &[flag(
+        "--background",
+        &[],
+        &[
+            "Rename targets instantly, delete them in a detached",
+            "worker (leftovers are resumed by the next run)",
+        ],
+    )],
+    &[
+        flag(
+            "--progress",
+            &[],
+            &["Live progress bar with ETA (terminal only)"],
+        ),
+        flag(
+            "--no-prescan",
+            &[],
+            &["Skip the pre-count; progress shows no ETA"],
+        ),
+    ],
+    &[
+        valued(
+            "--journal",
+            Value::Required("FILE"),
+            &["Record finished directories in FILE (removed on success)"],
+        ),
+        valued(
+            "--resume",
+            Value::Required("FILE"),
+            &["Continue an interrupted run from its journal"],
+        ),
+    ],
+    &[
+        valued(
+            "--max-ops-per-sec",
+            Value::Required("N"),
+            &["Limit unlink/rmdir calls per second"],
+        ),
+        valued(
+            "--max-bytes-per-sec",
+            Value::Required("SIZE"),
+            &["Limit bytes freed per second (e.g. 200M)"],
+        ),
+        flag(
+            "--nice",
+            &[],
+            &["Idle I/O priority and lowest CPU priority"],
+        ),
+    ],
+    &[
+        valued(
+            "--retries",
+            Value::Required("N"),
+            &[
+                "Retry busy files and directories that gain new",
+                "entries while being removed (default 3 once enabled)",
+            ],
+        ),
+        valued(
+            "--retry-delay",
+            Value::Required("MS"),
+            &[
+                "Wait before the first retry, doubled each time",
+                "(default 100)",
+            ],
+        ),
+    ],
+    &[
+        valued(
+            "--gradual-truncate",
+            Value::Optional("CHUNK"),
+            &[
+                "Shrink large files in CHUNK steps (default 256M)",
+                "before unlinking, to avoid filesystem stalls",
+            ],
+        ),
+        valued(
+            "--truncate-threshold",
+            Value::Required("SIZE"),
+            &["Only truncate files larger than SIZE (default 1G)"],
+        ),
+        valued(
+            "--truncate-pause",
+            Value::Required("MS"),
+            &["Pause between truncation steps"],
+        ),
+    ],
+    &[
+        valued(
+            "--fs-type",
+            Value::Required("TYPE"),
+            &[
+                "Use the profile of TYPE instead of detecting it",
+                "(tmpfs, ext4, xfs, btrfs, overlay, nfs, fuse, apfs)",
+            ],
+        ),
+        valued(
+            "--threads",
+            Value::Required("N"),
+            &["Worker threads (0 = one per CPU)"],
+        ),
+        valued(
+            "--parallel-threshold",
+            Value::Required("N"),
+            &["Directory size at which deletion goes parallel"],
+        ),
+        valued(
+            "--batch-size",
+            Value::Required("N"),
+            &["Minimum entries handed to a worker at once"],
+        ),
+    ],
+    &[
+        valued(
+            "--quoting-style",
+            Value::Required("STYLE"),
+            &[
+                "How file names are shown: literal, shell,",
+                "shell-escape (default), c, escape",
+            ],
+        ),
+        valued(
+            "--color",
+            Value::Optional("WHEN"),
+            &["Color output: auto (default), always or never"],
+        ),
+        flag(
+            "--ascii",
+            &[],
+            &["Plain ASCII output, without status glyphs"],
+        ),
+        valued(
+            "--compat",
+            Value::Required("gnu"),
+            &["Behave exactly like GNU rm (also when run as 'rm')"],
+        ),
+    ],
+    &[
+        flag("--version", &[], &["Show version"]),
+        flag("--help", &['h'], &["Show this help message"]),
+    ],
+];
+
+pub fn options() -> impl Iterator<Item = &'static OptionSpec> {
+    OPTION_GROUPS.iter().flat_map(|group| group.iter())
 }
 
-pub fn parse_flags(args: &[String]) -> Option<RmxArgs> {
-    if args.len() < 2 {
-        return None;
+/// Values an option accepts, when they are a fixed set
+pub fn choices(long: &str) -> Vec<&'static str> {
+    match long {
+        "--summary" => Summary::ALL.iter().map(|s| s.name()).collect(),
+        "--output" => OutputFormat::ALL.iter().map(|f| f.name()).collect(),
+        "--color" => ColorChoice::ALL.iter().map(|c| c.name()).collect(),
+        "--quoting-style" => QuotingStyle::ALL.iter().map(|s| s.name()).collect(),
+        "--fs-type" => FsKind::ALL.iter().map(|k| k.name()).collect(),
+        "--compat" => vec!["gnu"],
+        _ => Vec::new(),
     }
+}
+
+/// A word that replaces the options and paths, as in `rmx upgrade`
+#[derive(Debug, Clone, Copy)]
+pub struct Subcommand {
+    pub name: &'static str,
+    /// What follows the name, e.g. `[OPTIONS]`
+    pub usage: &'static str,
+    /// Help text, one entry per line; empty for unlisted commands
+    pub help: &'static [&'static str],
+    pub options: &'static [OptionSpec],
+    /// Fixed set of words accepted as the argument
+    pub args: &'static [&'static str],
+}
 
+const fn command(name: &'static str, help: &'static [&'static str]) -> Subcommand {
+    Subcommand {
+        name,
+        usage: "",
+        help,
+        options: &[],
+        args: &[],
+    }
+}
+
+/// Every subcommand, in the order shown by `--help`
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    command("help", &[]),
+    command("version", &[]),
+    command("about", &["Show program information"]),
+    command("dev", &["Show developer information"]),
+    command("upgrade", &["Upgrade to the latest version"]),
+    command("check-update", &["Check if a new version is available"]),
+    Subcommand {
+        usage: "[OPTIONS]",
+        options: crate::bench::OPTIONS,
+        ..command(
+            "bench",
+            &["Benchmark deletion strategies on a synthetic tree"],
+        )
+    },
+    Subcommand {
+        usage: "SHELL",
+        args: crate::completions::SHELLS,
+        ..command(
+            "completions",
+            &["Print the completion script for bash, zsh or fish"],
+        )
+    },
+    command("man", &["Print the manual page, in roff"]),
+];
+
+/// Raw arguments; file names need not be valid UTF-8
+pub fn parse_args() -> Vec<OsString> {
+    env::args_os().collect()
+}
+
+/// Parse the options and paths following the program name
+///
+/// Options and paths may be mixed; everything after `--` is a path, so
+/// `rmx -- -foo` removes a file named `-foo`. A lone `-` is a path too.
+pub fn parse_flags(args: &[OsString]) -> Result<RmxArgs, String> {
     let mut rmx_args = RmxArgs::default();
-    let mut i = 1;
-
-    while i < args.len() {
-        let arg = &args[i];
-
-        match arg.as_str() {
-            // Long flags
-            "--recursive" => rmx_args.recursive = true,
-            "--force" => rmx_args.force = true,
-            "--interactive" => rmx_args.interactive = true,
-            "--verbose" => rmx_args.verbose = true,
-            "--dir" => rmx_args.dir = true,
-            "--quiet" => rmx_args.quiet = true,
-
-            // Short flags (can be combined like -rf)
-            s if s.starts_with('-') && !s.starts_with("--") => {
-                for ch in s.chars().skip(1) {
-                    match ch {
-                        'r' | 'R' => rmx_args.recursive = true,
-                        'f' => rmx_args.force = true,
-                        'i' => rmx_args.interactive = true,
-                        'v' => rmx_args.verbose = true,
-                        'd' => rmx_args.dir = true,
-                        'q' => rmx_args.quiet = true,
-                        _ => {
-                            eprintln!("Unknown flag: -{}", ch);
-                            return None;
-                        }
-                    }
+    let mut paths = Vec::new();
+    let specs: Vec<_> = options().collect();
+
+    parse_command_line(
+        args,
+        &specs,
+        false,
+        |spec, value| match value {
+            None => {
+                set_flag(&mut rmx_args, spec.long);
+                Ok(())
+            }
+            Some(value) => apply_option(&mut rmx_args, spec.long, value),
+        },
+        &mut paths,
+    )?;
+
+    rmx_args.paths = paths;
+    Ok(rmx_args)
+}
+
+/// Split the arguments following the program name into options and paths
+///
+/// Handles `--name`, `--name=value`, `--name value`, combined short flags
+/// (`-rf`) and `--`. `on_option` gets a value only for options that take one.
+/// With `abbreviations`, a unique prefix of a long option is accepted like
+/// GNU `getopt_long` does (`--rec` for `--recursive`).
+pub fn parse_command_line(
+    args: &[OsString],
+    specs: &[&'static OptionSpec],
+    abbreviations: bool,
+    mut on_option: impl FnMut(&'static OptionSpec, Option<&OsStr>) -> Result<(), String>,
+    paths: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    let mut rest = args.iter().skip(1);
+
+    while let Some(arg) = rest.next() {
+        let bytes = arg.as_bytes();
+
+        if bytes == b"--" {
+            paths.extend(rest.map(PathBuf::from));
+            break;
+        }
+
+        // Long options: --name, --name=value, --name value
+        if let Some(long) = bytes.strip_prefix(b"--") {
+            let (name, inline) = match long.iter().position(|&b| b == b'=') {
+                Some(split) => (&long[..split], Some(OsStr::from_bytes(&long[split + 1..]))),
+                None => (long, None),
+            };
+            let spec = find_long(specs, name, abbreviations).map_err(|e| {
+                e.unwrap_or_else(|| format!("unrecognized option '{}'", arg.to_string_lossy()))
+            })?;
+
+            match (spec.value, inline) {
+                (Value::None, Some(_)) => {
+                    return Err(format!("option '{}' doesn't allow an argument", spec.long))
                 }
+                (Value::None | Value::Optional(_), None) => on_option(spec, None)?,
+                (Value::Required(_), None) => {
+                    let value = rest
+                        .next()
+                        .ok_or_else(|| format!("option '{}' requires an argument", spec.long))?;
+                    on_option(spec, Some(value))?;
+                }
+                (_, Some(value)) => on_option(spec, Some(value))?,
+            }
+            continue;
+        }
+
+        // Short flags (can be combined like -rf)
+        if bytes.len() > 1 && bytes[0] == b'-' {
+            for &b in &bytes[1..] {
+                let spec = specs
+                    .iter()
+                    .find(|o| b.is_ascii() && o.short.contains(&(b as char)))
+                    .ok_or_else(|| {
+                        format!("invalid option -- '{}'", String::from_utf8_lossy(&[b]))
+                    })?;
+                on_option(spec, None)?;
             }
+            continue;
+        }
+
+        // Paths
+        paths.push(PathBuf::from(arg));
+    }
 
-            // Paths
-            _ => {
-                rmx_args.paths.push(arg.clone());
+    Ok(())
+}
+
+/// Look up a long option by name (without the dashes)
+///
+/// `Err(None)` means unknown, `Err(Some(message))` an ambiguous abbreviation.
+fn find_long(
+    specs: &[&'static OptionSpec],
+    name: &[u8],
+    abbreviations: bool,
+) -> Result<&'static OptionSpec, Option<String>> {
+    let long = |spec: &&'static OptionSpec| spec.long.as_bytes().get(2..).unwrap_or_default();
+
+    if let Some(spec) = specs
+        .iter()
+        .find(|spec| !spec.long.is_empty() && long(spec) == name)
+    {
+        return Ok(spec);
+    }
+    if !abbreviations || name.is_empty() {
+        return Err(None);
+    }
+
+    let matches: Vec<_> = specs
+        .iter()
+        .filter(|spec| !spec.long.is_empty() && long(spec).starts_with(name))
+        .collect();
+    match matches.as_slice() {
+        [] => Err(None),
+        [spec] => Ok(spec),
+        several => {
+            let names: Vec<String> = several.iter().map(|s| format!("'{}'", s.long)).collect();
+            Err(Some(format!(
+                "option '--{}' is ambiguous; possibilities: {}",
+                String::from_utf8_lossy(name),
+                names.join(" ")
+            )))
+        }
+    }
+}
+
+/// Apply an option given without a value
+fn set_flag(rmx_args: &mut RmxArgs, long: &str) {
+    match long {
+        "--recursive" => rmx_args.recursive = true,
+        "--force" => rmx_args.force = true,
+        "--interactive" => rmx_args.interactive = true,
+        "--verbose" => rmx_args.verbose = true,
+        "--dir" => rmx_args.dir = true,
+        "--quiet" => rmx_args.summary = Summary::None,
+        "--background" => rmx_args.background = true,
+        "--nice" => rmx_args.nice = true,
+        "--progress" => rmx_args.progress = true,
+        "--no-prescan" => rmx_args.no_prescan = true,
+        "--gradual-truncate" => {
+            rmx_args
+                .gradual_truncate
+                .get_or_insert_with(GradualTruncate::default);
+        }
+        "--color" => rmx_args.color = ColorChoice::Always,
+        "--ascii" => rmx_args.ascii = true,
+        "--help" => rmx_args.show_help = true,
+        "--version" => rmx_args.show_version = true,
+        _ => unreachable!("option {} takes a value", long),
+    }
+}
+
+/// Apply an option given with a value
+fn apply_option(rmx_args: &mut RmxArgs, name: &str, value: &OsStr) -> Result<(), String> {
+    // Only paths may hold arbitrary bytes
+    match name {
+        "--journal" => {
+            rmx_args.journal = Some(PathBuf::from(value));
+            return Ok(());
+        }
+        "--resume" => {
+            rmx_args.resume = Some(PathBuf::from(value));
+            return Ok(());
+        }
+        "--events" => {
+            rmx_args.events = Some(PathBuf::from(value));
+            return Ok(());
+        }
+        _ => {}
+    }
+    let value = value
+        .to_str()
+        .ok_or_else(|| format!("Invalid value for {}: not valid UTF-8", name))?;
+
+    match name {
+        "--fs-type" => {
+            let kind = FsKind::from_name(value).ok_or_else(|| {
+                format!(
+                    "Unknown filesystem type: {} (expected one of: {})",
+                    value,
+                    choices(name).join(", ")
+                )
+            })?;
+            rmx_args.strategy.fs_kind = Some(kind);
+        }
+        "--threads" => rmx_args.strategy.threads = Some(parse_number(name, value)?),
+        "--parallel-threshold" => {
+            rmx_args.strategy.parallel_threshold = Some(parse_number(name, value)?)
+        }
+        "--batch-size" => rmx_args.strategy.batch_size = Some(parse_number(name, value)?),
+        "--max-ops-per-sec" => {
+            // 0 would not mean "no limit" but one operation per second
+            let ops: u64 = parse_number(name, value)?;
+            if ops == 0 {
+                return Err(format!("Invalid value for {}: {}", name, value));
             }
+            rmx_args.max_ops_per_sec = Some(ops);
+        }
+        "--max-bytes-per-sec" => {
+            let bytes = parse_size(value)
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Invalid value for {}: {}", name, value))?;
+            rmx_args.max_bytes_per_sec = Some(bytes);
+        }
+        "--gradual-truncate" => {
+            let chunk = parse_size(value)
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Invalid value for {}: {}", name, value))?;
+            rmx_args
+                .gradual_truncate
+                .get_or_insert_with(GradualTruncate::default)
+                .chunk = chunk;
+        }
+        "--truncate-threshold" => {
+            let threshold = parse_size(value)
+                .ok_or_else(|| format!("Invalid value for {}: {}", name, value))?;
+            rmx_args
+                .gradual_truncate
+                .get_or_insert_with(GradualTruncate::default)
+                .threshold = threshold;
+        }
+        "--truncate-pause" => {
+            let millis = parse_number(name, value)?;
+            rmx_args
+                .gradual_truncate
+                .get_or_insert_with(GradualTruncate::default)
+                .pause = Duration::from_millis(millis);
+        }
+        "--quoting-style" => {
+            rmx_args.quoting_style = QuotingStyle::from_name(value).ok_or_else(|| {
+                format!(
+                    "Unknown quoting style: {} (expected one of: {})",
+                    value,
+                    choices(name).join(", ")
+                )
+            })?;
+        }
+        "--color" => {
+            rmx_args.color = ColorChoice::from_name(value).ok_or_else(|| {
+                format!(
+                    "Unknown color choice: {} (expected one of: {})",
+                    value,
+                    choices(name).join(", ")
+                )
+            })?;
+        }
+        // Handled before parsing, see gnu::requested
+        "--compat" if value == "gnu" => {}
+        "--compat" => {
+            return Err(format!(
+                "Unknown compatibility mode: {} (expected: gnu)",
+                value
+            ))
+        }
+        "--summary" => {
+            rmx_args.summary = Summary::from_name(value).ok_or_else(|| {
+                format!(
+                    "Unknown summary level: {} (expected one of: {})",
+                    value,
+                    choices(name).join(", ")
+                )
+            })?;
+        }
+        "--format" => {
+            let template = EntryTemplate::parse(value)
+                .map_err(|e| format!("Invalid value for {}: {}", name, e))?;
+            rmx_args.format = Some(template);
         }
+        "--output" => {
+            rmx_args.output = OutputFormat::from_name(value).ok_or_else(|| {
+                format!(
+                    "Unknown output format: {} (expected one of: {})",
+                    value,
+                    choices(name).join(", ")
+                )
+            })?;
+        }
+        "--retries" => {
+            let attempts = parse_number(name, value)?;
+            rmx_args.retry.get_or_insert_with(Retry::default).attempts = attempts;
+        }
+        "--retry-delay" => {
+            let millis = parse_number(name, value)?;
+            rmx_args.retry.get_or_insert_with(Retry::default).delay = Duration::from_millis(millis);
+        }
+        _ => return Err(format!("option '{}' doesn't allow an argument", name)),
+    }
+    Ok(())
+}
+
+/// Parse into the target type, so an out-of-range value is an error too
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Options seen, with their values, and paths
+    type Parsed = (Vec<(&'static str, Option<String>)>, Vec<PathBuf>);
+
+    /// `args` (program name excluded) split by `parse_command_line`
+    fn parse(args: &[&str], abbreviations: bool) -> Result<Parsed, String> {
+        let args: Vec<OsString> = std::iter::once("rmx")
+            .chain(args.iter().copied())
+            .map(OsString::from)
+            .collect();
+        let specs: Vec<_> = options().collect();
+        let mut seen = Vec::new();
+        let mut paths = Vec::new();
+        parse_command_line(
+            &args,
+            &specs,
+            abbreviations,
+            |spec, value| {
+                seen.push((spec.long, value.map(|v| v.to_string_lossy().into_owned())));
+                Ok(())
+            },
+            &mut paths,
+        )?;
+        Ok((seen, paths))
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let (seen, paths) = parse(&["-r", "--", "-f", "--verbose", "--"], false).unwrap();
+        assert_eq!(seen, [("--recursive", None)]);
+        assert_eq!(
+            paths,
+            [Path::new("-f"), Path::new("--verbose"), Path::new("--")]
+        );
+    }
+
+    #[test]
+    fn lone_dash_is_a_path() {
+        let (seen, paths) = parse(&["-", "-v"], false).unwrap();
+        assert_eq!(seen, [("--verbose", None)]);
+        assert_eq!(paths, [Path::new("-")]);
+    }
 
-        i += 1;
+    #[test]
+    fn options_and_paths_mix() {
+        let (seen, paths) = parse(&["a", "-rf", "b"], false).unwrap();
+        assert_eq!(seen, [("--recursive", None), ("--force", None)]);
+        assert_eq!(paths, [Path::new("a"), Path::new("b")]);
     }
 
-    if rmx_args.paths.is_empty() {
-        return None;
+    #[test]
+    fn required_value_inline_or_next() {
+        let inline = parse(&["--retries=3", "a"], false).unwrap();
+        let separate = parse(&["--retries", "3", "a"], false).unwrap();
+        assert_eq!(inline, separate);
+        assert_eq!(inline.0, [("--retries", Some("3".to_string()))]);
+        assert_eq!(inline.1, [Path::new("a")]);
+
+        // The next argument is the value even when it looks like an option
+        let (seen, paths) = parse(&["--journal", "-v"], false).unwrap();
+        assert_eq!(seen, [("--journal", Some("-v".to_string()))]);
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn optional_value_only_inline() {
+        let (seen, paths) = parse(&["--color", "always"], false).unwrap();
+        assert_eq!(seen, [("--color", None)]);
+        assert_eq!(paths, [Path::new("always")]);
+
+        let (seen, _) = parse(&["--color=never"], false).unwrap();
+        assert_eq!(seen, [("--color", Some("never".to_string()))]);
+    }
+
+    #[test]
+    fn value_errors() {
+        assert_eq!(
+            parse(&["--retries"], false).unwrap_err(),
+            "option '--retries' requires an argument"
+        );
+        assert_eq!(
+            parse(&["--verbose=yes"], false).unwrap_err(),
+            "option '--verbose' doesn't allow an argument"
+        );
+    }
+
+    #[test]
+    fn unknown_options() {
+        assert_eq!(
+            parse(&["--frobnicate"], false).unwrap_err(),
+            "unrecognized option '--frobnicate'"
+        );
+        assert_eq!(
+            parse(&["--frobnicate=1"], false).unwrap_err(),
+            "unrecognized option '--frobnicate=1'"
+        );
+        assert_eq!(parse(&["-rZ"], false).unwrap_err(), "invalid option -- 'Z'");
     }
 
-    Some(rmx_args)
+    #[test]
+    fn abbreviations() {
+        let (seen, _) = parse(&["--verb", "--recur"], true).unwrap();
+        assert_eq!(seen, [("--verbose", None), ("--recursive", None)]);
+
+        // Without abbreviations a prefix is just unknown
+        assert_eq!(
+            parse(&["--verb"], false).unwrap_err(),
+            "unrecognized option '--verb'"
+        );
+    }
+
+    #[test]
+    fn exact_name_beats_longer_matches() {
+        static DIR: OptionSpec = flag("--dir", &[], &[]);
+        static DIRS: OptionSpec = flag("--dirs", &[], &[]);
+        let specs = [&DIRS, &DIR];
+        assert_eq!(find_long(&specs, b"dir", true).unwrap().long, "--dir");
+        assert_eq!(find_long(&specs, b"dirs", true).unwrap().long, "--dirs");
+        assert!(find_long(&specs, b"di", true).unwrap_err().is_some());
+    }
+
+    #[test]
+    fn ambiguous_abbreviation() {
+        let specs: Vec<_> = options().collect();
+        let message = find_long(&specs, b"re", true).unwrap_err().unwrap();
+        assert!(
+            message.starts_with("option '--re' is ambiguous; possibilities: "),
+            "{}",
+            message
+        );
+        assert!(message.contains("'--recursive'"), "{}", message);
+        assert!(message.contains("'--resume'"), "{}", message);
+
+        assert_eq!(find_long(&specs, b"nope", true).unwrap_err(), None);
+        assert_eq!(find_long(&specs, b"", true).unwrap_err(), None);
+    }
 }
@@ -0,0 +1,370 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::report::{JsonCounts, JsonError, JsonTarget};
+use crate::util::{lock, parse_size};
+use rmx_lib::template::user_name;
+use rmx_lib::DeleteStats;
+
+/// System-wide configuration, see README "Audit log"
+pub const CONFIG_PATH: &str = "/etc/rmx/audit.conf";
+
+#[cfg(target_os = "macos")]
+const SYSLOG_SOCKET: &str = "/var/run/syslog";
+#[cfg(not(target_os = "macos"))]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Failures listed by name in a record; the counts always cover all of them
+const MAX_ERRORS: usize = 100;
+
+/// Where audit records go
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sink {
+    /// Append-only file, rotated to FILE.1 .. FILE.keep past `max_size`
+    File {
+        path: PathBuf,
+        max_size: u64,
+        keep: u32,
+    },
+    /// Local syslog socket; journald listens on it too
+    Syslog {
+        socket: PathBuf,
+        facility: libc::c_int,
+    },
+}
+
+/// How a run removed its targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Rmx,
+    /// Targets renamed and queued for the background worker
+    Background,
+    /// GNU rm drop-in (`--compat=gnu` or run as `rm`)
+    Gnu,
+}
+
+/// Who ran what: the first fields of both records of a run
+#[derive(Serialize)]
+struct Run {
+    time: String,
+    /// `start` before anything is removed, `finish` once done
+    event: &'static str,
+    user: String,
+    uid: u32,
+    /// Who ran sudo, when run through it
+    sudo_user: Option<String>,
+    pid: u32,
+    cwd: String,
+    command: Vec<String>,
+    mode: &'static str,
+}
+
+/// Written before the run touches anything, so that even a killed run
+/// leaves a trace
+#[derive(Serialize)]
+struct StartRecord {
+    #[serde(flatten)]
+    run: Run,
+    targets: Vec<String>,
+}
+
+/// Written once the run is over
+#[derive(Serialize)]
+struct FinishRecord<'a> {
+    #[serde(flatten)]
+    run: Run,
+    #[serde(flatten)]
+    totals: JsonCounts<'a>,
+    errors: Vec<JsonError>,
+    targets: Vec<JsonTarget<'a>>,
+    interrupted: Option<&'static str>,
+}
+
+/// Sink read from the config on first use, shared by both records
+static SINK: OnceLock<Option<Sink>> = OnceLock::new();
+
+/// Log a run about to remove `targets`, if an audit sink is configured
+///
+/// A missing config means no auditing. Config and write errors are
+/// reported on stderr but never change the outcome of the run.
+pub fn start(mode: Mode, targets: &[PathBuf]) {
+    let record = StartRecord {
+        run: run(mode, "start"),
+        targets: targets
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+    };
+    write(&record);
+}
+
+/// Log the outcome of a run announced with `start`
+pub fn finish(
+    mode: Mode,
+    targets: &[(&PathBuf, DeleteStats)],
+    total: &DeleteStats,
+    interrupted: Option<&'static str>,
+) {
+    let record = FinishRecord {
+        run: run(mode, "finish"),
+        totals: JsonCounts::new(total),
+        errors: total
+            .errors
+            .iter()
+            .take(MAX_ERRORS)
+            .map(JsonError::new)
+            .collect(),
+        targets: targets
+            .iter()
+            .map(|(path, stats)| JsonTarget::new(path, stats))
+            .collect(),
+        interrupted,
+    };
+    write(&record);
+}
+
+fn run(mode: Mode, event: &'static str) -> Run {
+    let uid = unsafe { libc::getuid() };
+    Run {
+        time: utc_timestamp(),
+        event,
+        user: user_name(uid),
+        uid,
+        sudo_user: env::var("SUDO_USER").ok(),
+        pid: std::process::id(),
+        cwd: env::current_dir()
+            .map(|cwd| cwd.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        command: env::args_os()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect(),
+        mode: match mode {
+            Mode::Rmx => "rmx",
+            Mode::Background => "background",
+            Mode::Gnu => "gnu",
+        },
+    }
+}
+
+/// Send one record to the configured sink, if any
+fn write(record: &impl Serialize) {
+    let sink = SINK.get_or_init(|| match load(&config_path()) {
+        Ok(sink) => sink,
+        Err(e) => {
+            eprintln!("rmx: audit: {}", e);
+            None
+        }
+    });
+    let sink = match sink {
+        Some(sink) => sink,
+        None => return,
+    };
+
+    let line = match serde_json::to_string(record) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("rmx: audit: {}", e);
+            return;
+        }
+    };
+
+    let written = match sink {
+        Sink::File {
+            path,
+            max_size,
+            keep,
+        } => append(path, &line, *max_size, *keep),
+        Sink::Syslog { socket, facility } => send_syslog(socket, *facility, &line),
+    };
+    if let Err(e) = written {
+        eprintln!("rmx: audit: {}", e);
+    }
+}
+
+/// `RMX_AUDIT_CONFIG` is only honored for root (real and effective), so
+/// other users cannot switch the log off
+fn config_path() -> PathBuf {
+    let root = unsafe { libc::getuid() == 0 && libc::geteuid() == 0 };
+    match env::var_os("RMX_AUDIT_CONFIG") {
+        Some(config) if root => PathBuf::from(config),
+        _ => PathBuf::from(CONFIG_PATH),
+    }
+}
+
+/// Parse `key = value` lines; `#` starts a comment
+fn load(config: &Path) -> Result<Option<Sink>, String> {
+    let contents = match fs::read_to_string(config) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Cannot read {}: {}", config.display(), e)),
+    };
+
+    let mut sink = None;
+    let mut path = None;
+    let mut max_size = 10 * 1024 * 1024;
+    let mut keep = 5;
+    let mut socket = PathBuf::from(SYSLOG_SOCKET);
+    let mut facility = libc::LOG_AUTHPRIV;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let invalid = |what: &str| format!("{}:{}: {}", config.display(), number + 1, what);
+        let (key, value) = line
+            .split_once('=')
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .ok_or_else(|| invalid("expected key = value"))?;
+        match key {
+            "sink" => sink = Some(value.to_string()),
+            "path" => path = Some(PathBuf::from(value)),
+            "max_size" => {
+                max_size = parse_size(value).ok_or_else(|| invalid("invalid max_size"))?
+            }
+            "keep" => keep = value.parse().map_err(|_| invalid("invalid keep"))?,
+            "socket" => socket = PathBuf::from(value),
+            "facility" => {
+                facility = facility_code(value).ok_or_else(|| invalid("unknown facility"))?
+            }
+            _ => return Err(invalid(&format!("unknown key '{}'", key))),
+        }
+    }
+
+    match sink.as_deref() {
+        None | Some("off") => Ok(None),
+        Some("file") => {
+            let path =
+                path.ok_or_else(|| format!("{}: sink = file needs a path", config.display()))?;
+            Ok(Some(Sink::File {
+                path,
+                max_size,
+                keep,
+            }))
+        }
+        Some("syslog") => Ok(Some(Sink::Syslog { socket, facility })),
+        Some(other) => Err(format!(
+            "{}: unknown sink '{}' (expected one of: file, syslog, off)",
+            config.display(),
+            other
+        )),
+    }
+}
+
+fn facility_code(name: &str) -> Option<libc::c_int> {
+    Some(match name {
+        "auth" => libc::LOG_AUTH,
+        "authpriv" => libc::LOG_AUTHPRIV,
+        "user" => libc::LOG_USER,
+        "daemon" => libc::LOG_DAEMON,
+        "local0" => libc::LOG_LOCAL0,
+        "local1" => libc::LOG_LOCAL1,
+        "local2" => libc::LOG_LOCAL2,
+        "local3" => libc::LOG_LOCAL3,
+        "local4" => libc::LOG_LOCAL4,
+        "local5" => libc::LOG_LOCAL5,
+        "local6" => libc::LOG_LOCAL6,
+        "local7" => libc::LOG_LOCAL7,
+        _ => return None,
+    })
+}
+
+/// Append `line` in a single write, rotating first if the file is full
+///
+/// Concurrent runs serialize on an exclusive lock of the log itself; a run
+/// that waited while another one rotated reopens the new file.
+fn append(path: &Path, line: &str, max_size: u64, keep: u32) -> Result<(), String> {
+    let fail = |e: io::Error| format!("{}: {}", path.display(), e);
+    let open = || {
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(path)
+    };
+
+    let mut file = open().map_err(fail)?;
+    loop {
+        lock(&file, true);
+        // Rotated by another run while we waited for the lock
+        let current = fs::metadata(path).ok();
+        let ours = file.metadata().map_err(fail)?;
+        if current.is_some_and(|m| m.ino() == ours.ino() && m.dev() == ours.dev()) {
+            if max_size == 0 || ours.len() < max_size {
+                break;
+            }
+            rotate(path, keep).map_err(fail)?;
+        }
+        file = open().map_err(fail)?;
+    }
+
+    let mut record = line.as_bytes().to_vec();
+    record.push(b'\n');
+    file.write_all(&record).map_err(fail)
+}
+
+/// FILE.(keep-1) -> FILE.keep, ..., FILE -> FILE.1
+fn rotate(path: &Path, keep: u32) -> io::Result<()> {
+    let numbered = |n: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    for n in (1..keep).rev() {
+        match fs::rename(numbered(n), numbered(n + 1)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    fs::rename(path, numbered(1))
+}
+
+/// Send `line` as an informational message tagged `rmx[pid]`
+fn send_syslog(socket: &Path, facility: libc::c_int, line: &str) -> Result<(), String> {
+    let priority = facility | libc::LOG_INFO;
+    let message = format!("<{}>rmx[{}]: {}", priority, std::process::id(), line);
+    UnixDatagram::unbound()
+        .and_then(|sock| sock.send_to(message.as_bytes(), socket))
+        .map(|_| ())
+        .map_err(|e| format!("{}: {}", socket.display(), e))
+}
+
+/// Current time as `2024-05-01T12:34:56Z`
+fn utc_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rest) = (secs / 86400, secs % 86400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
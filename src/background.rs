@@ -0,0 +1,423 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::output::Color;
+use crate::util::lock;
+use rmx_lib::quote;
+use rmx_lib::{remove_directory_recursive, remove_file, RemoveOptions};
+
+/// Hidden subcommand the detached worker is started with
+pub const WORKER_COMMAND: &str = "__purge-tombstones";
+
+const TOMBSTONE_PREFIX: &str = ".rmx-tombstone";
+
+/// Worker runs that may fail on a tombstone before it is given up on
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Directory holding the tombstone registry and the worker lock
+///
+/// Never a shared directory such as /tmp: whoever can write the registry
+/// picks what the worker deletes.
+fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_STATE_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir).join("rmx"));
+    }
+    if let Some(home) = env::var_os("HOME").filter(|h| !h.is_empty()) {
+        return Some(PathBuf::from(home).join(".local/state/rmx"));
+    }
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|d| !d.is_empty())
+        .map(|dir| PathBuf::from(dir).join("rmx"))
+}
+
+/// Create the state directory, or make sure an existing one is ours alone
+fn open_state_dir() -> Result<PathBuf, String> {
+    let dir = state_dir().ok_or_else(|| {
+        "No directory for background state: set HOME or XDG_STATE_HOME".to_string()
+    })?;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", quote::quote(&dir), e))?;
+
+    let metadata = fs::symlink_metadata(&dir)
+        .map_err(|e| format!("Cannot access {}: {}", quote::quote(&dir), e))?;
+    if !metadata.is_dir() || metadata.uid() != unsafe { libc::getuid() } {
+        return Err(format!(
+            "Refusing to use {}: not a directory owned by the current user",
+            quote::quote(&dir)
+        ));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Cannot restrict {}: {}", quote::quote(&dir), e))?;
+    }
+    Ok(dir)
+}
+
+fn registry_path(dir: &Path) -> PathBuf {
+    dir.join("tombstones")
+}
+
+/// Tombstones given up on, reported by the next foreground run
+fn given_up_path(dir: &Path) -> PathBuf {
+    dir.join("given-up")
+}
+
+/// Only paths made by `bury` are ever deleted by the worker
+fn is_tombstone(path: &Path) -> bool {
+    path.is_absolute()
+        && path.file_name().is_some_and(|name| {
+            name.as_bytes()
+                .starts_with(format!("{}.", TOMBSTONE_PREFIX).as_bytes())
+        })
+}
+
+/// Check that tombstones can be registered, before any target is renamed
+pub fn prepare() -> Result<(), String> {
+    open_state_dir().map(|_| ())
+}
+
+/// Atomically rename a target to a hidden tombstone next to it
+pub fn bury(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Cannot move {} to background", quote::quote(path)))?;
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let parent = fs::canonicalize(&parent)
+        .map_err(|e| format!("Cannot access {}: {}", quote::quote(&parent), e))?;
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut tombstone_name = OsString::from(format!(
+        "{}.{}.{}.",
+        TOMBSTONE_PREFIX,
+        std::process::id(),
+        nanos
+    ));
+    tombstone_name.push(name);
+    let tombstone = parent.join(tombstone_name);
+
+    fs::rename(path, &tombstone)
+        .map_err(|e| format!("Cannot move {} to background: {}", quote::quote(path), e))?;
+
+    Ok(tombstone)
+}
+
+/// Record tombstones so any later rmx run can finish them
+pub fn register(tombstones: &[PathBuf]) -> Result<(), String> {
+    let registry = registry_path(&open_state_dir()?);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(&registry)
+        .map_err(|e| format!("Failed to open {}: {}", quote::quote(&registry), e))?;
+    lock(&file, true);
+
+    let entries: Vec<_> = tombstones.iter().map(|t| (t.clone(), 0)).collect();
+    file.write_all(&encode(&entries))
+        .map_err(|e| format!("Failed to write {}: {}", quote::quote(&registry), e))?;
+
+    Ok(())
+}
+
+/// Start a detached worker that deletes every registered tombstone
+pub fn spawn_worker() -> Result<(), String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to get current path: {}", e))?;
+
+    let mut command = Command::new(exe);
+    command
+        .arg(WORKER_COMMAND)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // New session so the worker survives the terminal and CI step closing
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start background worker: {}", e))
+}
+
+/// Report tombstones the worker gave up on, and restart a worker if an
+/// earlier run left tombstones behind and none is running
+pub fn resume_pending() {
+    let dir = match state_dir() {
+        Some(dir) => dir,
+        None => return,
+    };
+    report_given_up(&dir);
+
+    let pending = fs::metadata(registry_path(&dir)).is_ok_and(|m| m.len() > 0);
+    if pending && !worker_running(&dir) {
+        let _ = spawn_worker();
+    }
+}
+
+/// Tell the user about tombstones left for good, once
+fn report_given_up(dir: &Path) {
+    let path = given_up_path(dir);
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    let _ = fs::remove_file(&path);
+
+    for (tombstone, attempts) in decode(&contents) {
+        eprintln!(
+            "{}",
+            Color::Yellow.paint_err(format_args!(
+                "rmx: background deletion gave up on {} after {} attempts; remove it by hand",
+                quote::quote(&tombstone),
+                attempts
+            ))
+        );
+    }
+}
+
+/// Whether a worker holds the lock of the registry in `dir`
+fn worker_running(dir: &Path) -> bool {
+    File::open(dir.join("worker.lock")).is_ok_and(|file| !lock(&file, false))
+}
+
+/// Worker entry point: delete tombstones until the registry is empty
+pub fn purge_tombstones() {
+    let dir = match open_state_dir() {
+        Ok(dir) => dir,
+        Err(_) => return,
+    };
+
+    // Only one worker at a time; the others just exit
+    let worker_lock = match File::create(dir.join("worker.lock")) {
+        Ok(f) => f,
+        Err(_) => return,
+    };
+    if !lock(&worker_lock, false) {
+        return;
+    }
+
+    let opts = RemoveOptions {
+        force: true,
+        ..RemoveOptions::default()
+    };
+
+    loop {
+        let tombstones: Vec<PathBuf> = read_registry(&dir).into_iter().map(|(t, _)| t).collect();
+        for tombstone in &tombstones {
+            delete_tombstone(tombstone, &opts);
+        }
+
+        if !forget(&dir, &tombstones) {
+            break;
+        }
+    }
+}
+
+/// One entry per line: failed attempts, a space and the raw path, so
+/// non-UTF-8 names survive the round trip
+fn encode(entries: &[(PathBuf, u32)]) -> Vec<u8> {
+    let mut contents = Vec::new();
+    for (tombstone, attempts) in entries {
+        contents.extend_from_slice(format!("{} ", attempts).as_bytes());
+        contents.extend_from_slice(tombstone.as_os_str().as_bytes());
+        contents.push(b'\n');
+    }
+    contents
+}
+
+/// Entries written by `encode`; a bare path (older registries) has no
+/// failed attempts yet
+fn decode(contents: &[u8]) -> Vec<(PathBuf, u32)> {
+    contents
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let counted = line.iter().position(|&b| b == b' ').and_then(|space| {
+                let attempts = std::str::from_utf8(&line[..space]).ok()?.parse().ok()?;
+                Some((&line[space + 1..], attempts))
+            });
+            let (path, attempts) = counted.unwrap_or((line, 0));
+            (PathBuf::from(OsStr::from_bytes(path)), attempts)
+        })
+        .collect()
+}
+
+/// Registered tombstones; anything else in the file is ignored
+fn read_registry(dir: &Path) -> Vec<(PathBuf, u32)> {
+    let contents = fs::read(registry_path(dir)).unwrap_or_default();
+    decode(&contents)
+        .into_iter()
+        .filter(|(path, _)| is_tombstone(path))
+        .collect()
+}
+
+/// Drop finished tombstones from the registry, keeping entries appended
+/// meanwhile; returns whether there are new ones to work on
+///
+/// A tombstone still there after `MAX_ATTEMPTS` runs moves to the given-up
+/// list, so that no worker retries it forever.
+fn forget(dir: &Path, done: &[PathBuf]) -> bool {
+    let registry = registry_path(dir);
+    let file = match OpenOptions::new().read(true).write(true).open(&registry) {
+        Ok(f) => f,
+        Err(_) => return false,
+    };
+    lock(&file, true);
+
+    let mut remaining = Vec::new();
+    let mut given_up = Vec::new();
+    for (tombstone, attempts) in read_registry(dir) {
+        if !done.contains(&tombstone) {
+            remaining.push((tombstone, attempts));
+        } else if tombstone.symlink_metadata().is_ok() {
+            if attempts + 1 >= MAX_ATTEMPTS {
+                given_up.push((tombstone, attempts + 1));
+            } else {
+                remaining.push((tombstone, attempts + 1));
+            }
+        }
+    }
+
+    if !given_up.is_empty() {
+        let _ = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(given_up_path(dir))
+            .and_then(|mut file| file.write_all(&encode(&given_up)));
+    }
+    if file.set_len(0).is_err() || (&file).write_all(&encode(&remaining)).is_err() {
+        return false;
+    }
+
+    // Stop instead of spinning on tombstones that cannot be deleted
+    remaining.iter().any(|(t, _)| !done.contains(t))
+}
+
+fn delete_tombstone(path: &Path, opts: &RemoveOptions) {
+    let metadata = match path.symlink_metadata() {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    if metadata.is_dir() {
+        let _ = remove_directory_recursive(path, opts);
+        let _ = fs::remove_dir(path);
+    } else {
+        let _ = remove_file(path, opts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_absolute_tombstone_names_qualify() {
+        assert!(is_tombstone(Path::new("/srv/.rmx-tombstone.12.345.build")));
+        assert!(is_tombstone(Path::new("/.rmx-tombstone.x")));
+
+        assert!(!is_tombstone(Path::new(".rmx-tombstone.12.345.build")));
+        assert!(!is_tombstone(Path::new("srv/.rmx-tombstone.12.345.build")));
+        assert!(!is_tombstone(Path::new("/srv/.rmx-tombstone")));
+        assert!(!is_tombstone(Path::new("/srv/.rmx-tombstones")));
+        assert!(!is_tombstone(Path::new("/srv/build")));
+        assert!(!is_tombstone(Path::new("/.rmx-tombstone.x/..")));
+        assert!(!is_tombstone(Path::new("/")));
+        assert!(!is_tombstone(Path::new("")));
+    }
+
+    #[test]
+    fn registry_round_trips() {
+        let entries = vec![
+            (PathBuf::from("/srv/.rmx-tombstone.1.2.a b"), 0),
+            (
+                PathBuf::from(OsStr::from_bytes(b"/srv/.rmx-tombstone.1.3.\xff")),
+                4,
+            ),
+        ];
+        assert_eq!(decode(&encode(&entries)), entries);
+        assert!(decode(b"").is_empty());
+    }
+
+    #[test]
+    fn bare_paths_have_no_attempts() {
+        let decoded = decode(b"/srv/.rmx-tombstone.1.2.a\n\n2 /srv/.rmx-tombstone.1.3.b\n/x y\n");
+        assert_eq!(
+            decoded,
+            vec![
+                (PathBuf::from("/srv/.rmx-tombstone.1.2.a"), 0),
+                (PathBuf::from("/srv/.rmx-tombstone.1.3.b"), 2),
+                (PathBuf::from("/x y"), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn forget_gives_up_after_max_attempts() {
+        let dir = env::temp_dir().join(format!("rmx-background-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let stuck = dir.join(".rmx-tombstone.1.2.stuck");
+        let gone = dir.join(".rmx-tombstone.1.3.gone");
+        let stranger = dir.join("not-a-tombstone");
+        fs::create_dir(&stuck).unwrap();
+        fs::write(
+            registry_path(&dir),
+            encode(&[(stuck.clone(), 0), (gone.clone(), 0), (stranger, 0)]),
+        )
+        .unwrap();
+
+        let done = [stuck.clone(), gone];
+        for attempt in 1..MAX_ATTEMPTS {
+            assert!(!forget(&dir, &done));
+            assert_eq!(read_registry(&dir), vec![(stuck.clone(), attempt)]);
+        }
+        assert!(!forget(&dir, &done));
+        assert!(read_registry(&dir).is_empty());
+
+        let given_up = fs::read(given_up_path(&dir)).unwrap();
+        assert_eq!(decode(&given_up), vec![(stuck, MAX_ATTEMPTS)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn forget_reports_new_entries() {
+        let dir = env::temp_dir().join(format!("rmx-background-new-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let appended = dir.join(".rmx-tombstone.1.4.new");
+        fs::write(registry_path(&dir), encode(&[(appended.clone(), 0)])).unwrap();
+
+        assert!(forget(&dir, &[]));
+        assert_eq!(read_registry(&dir), vec![(appended, 0)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
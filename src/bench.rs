@@ -0,0 +1,411 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::args::{flag, parse_command_line, valued, OptionSpec, Value};
+use crate::output::{Color, Mark};
+use crate::util::{bytes_to_readable, parse_size};
+use rmx_lib::fs_profile::{self, StrategyOverrides};
+use rmx_lib::quote;
+use rmx_lib::{remove_directory_recursive, RemoveOptions};
+
+/// Options of `rmx bench`
+pub const OPTIONS: &[OptionSpec] = &[
+    valued(
+        "--width",
+        Value::Required("N"),
+        &["Subdirectories per directory (default 4)"],
+    ),
+    valued(
+        "--depth",
+        Value::Required("N"),
+        &["Levels of subdirectories (default 3)"],
+    ),
+    valued(
+        "--files",
+        Value::Required("N"),
+        &["Files per directory (default 100)"],
+    ),
+    valued(
+        "--size",
+        Value::Required("SIZE"),
+        &["Bytes per file (default 1K)"],
+    ),
+    valued(
+        "--threads",
+        Value::Required("N,N,.."),
+        &["Thread counts to measure (default 2, 4 and every core)"],
+    ),
+    valued(
+        "--runs",
+        Value::Required("N"),
+        &["Repetitions per strategy, best time wins (default 1)"],
+    ),
+    valued(
+        "--dir",
+        Value::Required("PATH"),
+        &["Where to build the tree; decides the filesystem"],
+    ),
+    flag("--compare-rm", &[], &["Also time rm -rf"]),
+    flag("--json", &[], &["Print the results as JSON"]),
+];
+
+/// Shape of the synthetic tree and what to measure
+#[derive(Debug, Clone)]
+struct BenchConfig {
+    width: usize,        // --width (subdirectories per directory)
+    depth: usize,        // --depth (levels of subdirectories)
+    files: usize,        // --files (files per directory)
+    file_size: u64,      // --size (bytes per file)
+    threads: Vec<usize>, // --threads=1,2,4
+    runs: usize,         // --runs (repetitions per strategy, best time wins)
+    dir: PathBuf,        // --dir (scratch directory, decides the filesystem)
+    compare_rm: bool,    // --compare-rm
+    json: bool,          // --json
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let mut threads = vec![2, 4, cpus];
+        threads.sort_unstable();
+        threads.dedup();
+
+        BenchConfig {
+            width: 4,
+            depth: 3,
+            files: 100,
+            file_size: 1024,
+            threads,
+            runs: 1,
+            dir: env::temp_dir(),
+            compare_rm: false,
+            json: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BenchResult {
+    strategy: String,
+    threads: usize,
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+    seconds: f64,
+    files_per_sec: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    version: &'static str,
+    filesystem: &'static str,
+    width: usize,
+    depth: usize,
+    files_per_dir: usize,
+    file_size: u64,
+    results: Vec<BenchResult>,
+}
+
+/// Entry point for `rmx bench [OPTIONS]`; `args` start with `bench`
+pub fn run(args: &[OsString]) -> Result<(), String> {
+    let config = parse_bench_args(args)?;
+
+    fs::create_dir_all(&config.dir)
+        .map_err(|e| format!("Failed to create {}: {}", quote::quote(&config.dir), e))?;
+    let scratch = config.dir.join(format!("rmx-bench-{}", std::process::id()));
+
+    let filesystem = fs_profile::detect(&config.dir).name();
+
+    if !config.json {
+        print_header();
+    }
+
+    let results = measure(&config, &scratch);
+
+    // Never leave the scratch tree behind, even if a strategy failed midway
+    let _ = fs::remove_dir_all(&scratch);
+    let results = results?;
+
+    if config.json {
+        let report = BenchReport {
+            version: env!("CARGO_PKG_VERSION"),
+            filesystem,
+            width: config.width,
+            depth: config.depth,
+            files_per_dir: config.files,
+            file_size: config.file_size,
+            results,
+        };
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to encode results: {}", e))?;
+        println!("{}", json);
+    } else {
+        print_footer(&results, filesystem);
+    }
+
+    Ok(())
+}
+
+/// Run every strategy on a freshly generated tree, keeping the best run
+fn measure(config: &BenchConfig, scratch: &Path) -> Result<Vec<BenchResult>, String> {
+    let mut results = Vec::new();
+
+    for (strategy, threads, overrides) in strategies(config) {
+        let mut best: Option<BenchResult> = None;
+
+        for _ in 0..config.runs {
+            let (files, dirs) = generate_tree(scratch, config)?;
+
+            let elapsed = if strategy == "rm -rf" {
+                time_rm(scratch)?
+            } else {
+                time_rmx(scratch, &overrides)?
+            };
+
+            let seconds = elapsed.as_secs_f64();
+            let result = BenchResult {
+                strategy: strategy.to_string(),
+                threads,
+                files,
+                dirs,
+                bytes: files as u64 * config.file_size,
+                seconds,
+                files_per_sec: if seconds > 0.0 {
+                    files as f64 / seconds
+                } else {
+                    0.0
+                },
+            };
+
+            if best.as_ref().is_none_or(|b| result.seconds < b.seconds) {
+                best = Some(result);
+            }
+        }
+
+        if !config.json {
+            if let Some(result) = &best {
+                print_row(result);
+            }
+        }
+        results.extend(best);
+    }
+
+    Ok(results)
+}
+
+/// `args` start with `bench` itself, like a program name
+fn parse_bench_args(args: &[OsString]) -> Result<BenchConfig, String> {
+    let mut config = BenchConfig::default();
+    let mut extra = Vec::new();
+    let specs: Vec<_> = OPTIONS.iter().collect();
+
+    parse_command_line(
+        args,
+        &specs,
+        false,
+        |spec, value| {
+            let name = spec.long;
+            let text = value.map(|v| v.to_string_lossy()).unwrap_or_default();
+            let number = |value: &str| {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid value for {}: {}", name, value))
+            };
+
+            match name {
+                "--width" => config.width = number(&text)?,
+                "--depth" => config.depth = number(&text)?,
+                "--files" => config.files = number(&text)?,
+                "--runs" => config.runs = number(&text)?.max(1),
+                "--size" => {
+                    config.file_size = parse_size(&text)
+                        .ok_or_else(|| format!("Invalid value for {}: {}", name, text))?
+                }
+                "--threads" => {
+                    config.threads = text.split(',').map(number).collect::<Result<Vec<_>, _>>()?
+                }
+                "--dir" => config.dir = PathBuf::from(value.unwrap_or_default()),
+                "--compare-rm" => config.compare_rm = true,
+                "--json" => config.json = true,
+                _ => unreachable!("unhandled option {}", name),
+            }
+            Ok(())
+        },
+        &mut extra,
+    )?;
+
+    if let Some(arg) = extra.first() {
+        return Err(format!("Unexpected argument: {}", quote::quote(arg)));
+    }
+    Ok(config)
+}
+
+/// Every strategy worth measuring: (label, threads, overrides)
+fn strategies(config: &BenchConfig) -> Vec<(&'static str, usize, StrategyOverrides)> {
+    let mut list = vec![
+        (
+            "sequential",
+            1,
+            StrategyOverrides {
+                parallel_threshold: Some(usize::MAX),
+                threads: Some(1),
+                ..StrategyOverrides::default()
+            },
+        ),
+        ("auto", 0, StrategyOverrides::default()),
+    ];
+
+    for &threads in &config.threads {
+        list.push((
+            "parallel",
+            threads,
+            StrategyOverrides {
+                parallel_threshold: Some(0),
+                threads: Some(threads),
+                ..StrategyOverrides::default()
+            },
+        ));
+    }
+
+    if config.compare_rm {
+        list.push(("rm -rf", 1, StrategyOverrides::default()));
+    }
+
+    list
+}
+
+/// Build the synthetic tree, returning (files, dirs) created
+fn generate_tree(root: &Path, config: &BenchConfig) -> Result<(usize, usize), String> {
+    let content = vec![b'x'; config.file_size as usize];
+    let mut files = 0;
+    let mut dirs = 0;
+    let mut pending = vec![(root.to_path_buf(), 0)];
+
+    while let Some((dir, level)) = pending.pop() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", quote::quote(&dir), e))?;
+        dirs += 1;
+
+        for i in 0..config.files {
+            let file = dir.join(format!("file_{}.dat", i));
+            fs::write(&file, &content)
+                .map_err(|e| format!("Failed to write {}: {}", quote::quote(&file), e))?;
+            files += 1;
+        }
+
+        if level < config.depth {
+            for i in 0..config.width {
+                pending.push((dir.join(format!("dir_{}", i)), level + 1));
+            }
+        }
+    }
+
+    Ok((files, dirs))
+}
+
+fn time_rmx(root: &Path, overrides: &StrategyOverrides) -> Result<Duration, String> {
+    let opts = RemoveOptions {
+        strategy: overrides.clone(),
+        ..RemoveOptions::default()
+    };
+
+    let start = Instant::now();
+    let stats = remove_directory_recursive(root, &opts).map_err(|e| e.to_string())?;
+    // A partial deletion would be timed as a fast one
+    if stats.failed_count() > 0 {
+        let first = stats.errors.first().map(|e| format!(": {}", e));
+        return Err(format!(
+            "{} entries could not be removed{}",
+            stats.failed_count(),
+            first.unwrap_or_default()
+        ));
+    }
+    fs::remove_dir(root)
+        .map_err(|e| format!("Cannot remove directory {}: {}", quote::quote(root), e))?;
+    Ok(start.elapsed())
+}
+
+fn time_rm(root: &Path) -> Result<Duration, String> {
+    let start = Instant::now();
+    let status = Command::new("/bin/rm")
+        .arg("-rf")
+        .arg(root)
+        .status()
+        .map_err(|e| format!("Failed to run /bin/rm: {}", e))?;
+    let elapsed = start.elapsed();
+
+    if !status.success() {
+        return Err(format!("/bin/rm exited with {}", status));
+    }
+    Ok(elapsed)
+}
+
+fn threads_label(threads: usize) -> String {
+    if threads == 0 {
+        "auto".to_string()
+    } else {
+        threads.to_string()
+    }
+}
+
+fn print_row(result: &BenchResult) {
+    println!(
+        "{:<12} {:>7} {:>9} {:>10} {:>12.3}s {:>12.0}/s",
+        result.strategy,
+        threads_label(result.threads),
+        result.files,
+        bytes_to_readable(result.bytes),
+        result.seconds,
+        result.files_per_sec
+    );
+}
+
+fn print_footer(results: &[BenchResult], filesystem: &str) {
+    let fastest = results
+        .iter()
+        .min_by(|a, b| a.seconds.total_cmp(&b.seconds));
+    let rm = results.iter().find(|r| r.strategy == "rm -rf");
+
+    if let Some(best) = fastest {
+        println!(
+            "\n{}",
+            Color::Green.paint(format_args!(
+                "{}Fastest: {} ({} threads) on {}",
+                Mark::Ok,
+                best.strategy,
+                threads_label(best.threads),
+                filesystem
+            ))
+        );
+        if let Some(rm) = rm {
+            if best.seconds > 0.0 {
+                println!(
+                    "{}",
+                    Color::Green.paint(format_args!(
+                        "{}Speedup vs rm: {:.2}x",
+                        Mark::Ok,
+                        rm.seconds / best.seconds
+                    ))
+                );
+            }
+        }
+    }
+}
+
+fn print_header() {
+    println!(
+        "{}",
+        Color::Cyan.paint(format_args!(
+            "{:<12} {:>7} {:>9} {:>10} {:>13} {:>14}",
+            "STRATEGY", "THREADS", "FILES", "SIZE", "TIME", "RATE"
+        ))
+    );
+}
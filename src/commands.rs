@@ -1,236 +1,778 @@
-use std::path::Path;
+use std::env;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 
-use termion::{color, style};
-
-use crate::args::RmxArgs;
+use crate::args::{self, OutputFormat, RmxArgs, Summary, Value};
+use crate::audit;
+use crate::background;
+use crate::output::{self, Color, Mark};
+use crate::progress::ProgressDisplay;
+use crate::report;
+use crate::signals;
 use crate::util::bytes_to_readable;
+use rmx_lib::events::{Event, EventSink};
+use rmx_lib::journal::Journal;
+use rmx_lib::quote::{self, QuotingStyle};
+use rmx_lib::throttle;
 use rmx_lib::{
-    remove_directory_interactive, remove_directory_recursive, remove_empty_directory, remove_file,
-    remove_file_interactive, DeleteStats,
+    prompt_user, remove_directory_interactive, remove_directory_tree, remove_empty_directory,
+    remove_file, remove_file_interactive, scan_tree, AtomicStats, DeleteStats, Operation,
+    RemoveOptions, RmxError,
 };
 
 pub fn execute_removal(args: &RmxArgs) {
+    quote::set_style(args.quoting_style);
+
+    if let Some((option, owner)) = stdout_conflict(args) {
+        eprintln!(
+            "{}",
+            Color::Red.paint_err(format_args!(
+                "rmx: {} is not supported with {}",
+                option, owner
+            ))
+        );
+        std::process::exit(1);
+    }
+
+    // Finish tombstones left behind by an earlier --background run
+    background::resume_pending();
+
+    if args.background {
+        let unsupported = if args.output == OutputFormat::Json {
+            Some("--output=json")
+        } else if args.events.is_some() {
+            Some("--events")
+        } else {
+            None
+        };
+        if let Some(option) = unsupported {
+            eprintln!(
+                "{}",
+                Color::Red.paint_err(format_args!(
+                    "rmx: {} is not supported with --background",
+                    option
+                ))
+            );
+            std::process::exit(1);
+        }
+        execute_background(args);
+        return;
+    }
+
+    if args.nice {
+        throttle::lower_priority();
+    }
+
+    // Live counters for the progress line and SIGUSR1 snapshots
+    let live = Arc::new(AtomicStats::new());
+
+    // Before any thread exists, so workers inherit the blocked signal mask
+    let cancel = signals::install(Arc::clone(&live));
+
+    let opened = open_journal(args).and_then(|(journal, paths)| {
+        let events = open_events(args)?;
+        Ok((journal, paths, events))
+    });
+    let (journal, paths, events) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            eprintln!("{}", Color::Red.paint_err(format_args!("rmx: {}", e)));
+            std::process::exit(1);
+        }
+    };
+
+    audit::start(audit::Mode::Rmx, &paths);
+
     let start_time = Instant::now();
     let mut total_stats = DeleteStats::new();
+    let mut opts = args.remove_options();
+    opts.cancel = Some(cancel);
+    opts.progress = Some(Arc::clone(&live));
+    opts.journal = journal.clone();
+    opts.events = events.clone();
+    let progress = start_progress(args, &paths, live);
+    let mut incomplete: Vec<&PathBuf> = Vec::new();
+    // Per target, for --summary=full, --output=json and the audit log
+    let mut breakdown: Vec<(&PathBuf, DeleteStats)> = Vec::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        if opts.is_cancelled() {
+            incomplete.extend(&paths[index..]);
+            break;
+        }
+
+        // Check if path exists, dangling symlinks included (targets finished
+        // before a resume are gone)
+        let metadata = match path.symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                if !args.force && args.resume.is_none() {
+                    let e = RmxError::from_errno(Operation::Remove, path, libc::ENOENT);
+                    report_error(&e);
+                    if let Some(events) = &events {
+                        events.emit(Event::Error(&e));
+                    }
+                    let mut stats = DeleteStats::new();
+                    stats.record_failure(e, false);
+                    breakdown.push((path, stats.clone()));
+                    total_stats.merge(stats);
+                }
+                continue;
+            }
+        };
+
+        // Handle based on file type and flags; a symlink is removed itself,
+        // even when it points to a directory
+        let result = if metadata.is_dir() {
+            handle_directory(path, args, &opts)
+        } else {
+            handle_file(path, args, &opts)
+        };
+
+        // An interrupted target is left partially deleted; report, don't fail
+        if opts.is_cancelled() {
+            if let Ok(stats) = result {
+                total_stats.merge(stats);
+            }
+            if path.symlink_metadata().is_ok() {
+                incomplete.push(path);
+            }
+            incomplete.extend(&paths[index + 1..]);
+            break;
+        }
+
+        let stats = match result {
+            Ok(stats) => {
+                for e in &stats.errors {
+                    report_error(e);
+                }
+                stats
+            }
+            Err(e) => {
+                report_error(&e);
+                let mut stats = DeleteStats::new();
+                stats.record_failure(e, args.force);
+                stats
+            }
+        };
+        breakdown.push((path, stats.clone()));
+        total_stats.merge(stats);
+    }
+
+    let elapsed_time = start_time.elapsed();
+
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+
+    if let Some(events) = &events {
+        if let Err(e) = events.finish() {
+            eprintln!(
+                "{}",
+                Color::Red.paint_err(format_args!("rmx: Failed to write events: {}", e))
+            );
+        }
+    }
+
+    // Print summary
+    match args.output {
+        OutputFormat::Json => report::print_json(
+            &total_stats,
+            elapsed_time,
+            &breakdown,
+            signals::received().map(signals::name),
+            &incomplete,
+        ),
+        // The event stream keeps stdout to itself
+        OutputFormat::Text if events_to_stdout(args) => {}
+        OutputFormat::Text => {
+            if args.summary != Summary::None {
+                print_summary(&total_stats, elapsed_time);
+            }
+            if args.summary == Summary::Full {
+                print_breakdown(&breakdown);
+            }
+        }
+    }
+
+    audit::finish(
+        audit::Mode::Rmx,
+        &breakdown,
+        &total_stats,
+        signals::received().map(signals::name),
+    );
+
+    if let Some(signal) = signals::received() {
+        print_interrupted(signal, &incomplete);
+        if let Some(journal) = &journal {
+            journal.sync();
+            eprintln!("Resume with: rmx {}", resume_hint(args));
+        }
+        std::process::exit(signals::exit_code(signal));
+    }
+
+    if !total_stats.errors.is_empty() {
+        // The journal stays, a resumed run retries what failed
+        if args.output == OutputFormat::Text {
+            print_errors(&total_stats.errors);
+        }
+        if let Some(journal) = &journal {
+            journal.sync();
+        }
+        std::process::exit(1);
+    }
+
+    if let Some(journal) = &journal {
+        // Failures ignored by -f still leave directories to resume
+        if total_stats.failed_count() > 0 {
+            journal.sync();
+        } else if let Err(e) = journal.finish() {
+            report_error(&e);
+        }
+    }
+}
+
+/// Print a failure as soon as it is known
+fn report_error(e: &RmxError) {
+    eprintln!("{}", Color::Red.paint_err(render_error(e)));
+}
+
+/// Recap of every failure, after the summary
+fn print_errors(errors: &[RmxError]) {
+    eprintln!("{}", Color::Red.paint_err("Could not remove:"));
+    for e in errors {
+        eprintln!("  {}: {}", quote::quote(e.path()), e.description());
+    }
+}
+
+/// Open the --journal/--resume file and work out the targets to delete
+fn open_journal(args: &RmxArgs) -> Result<(Option<Arc<Journal>>, Vec<PathBuf>), String> {
+    if let Some(file) = &args.resume {
+        if !args.paths.is_empty() {
+            return Err("--resume takes its targets from the journal".to_string());
+        }
+        let (journal, state) = Journal::resume(file).map_err(|e| e.to_string())?;
+        env::set_current_dir(&state.cwd)
+            .map_err(|e| format!("Cannot enter {}: {}", quote::quote(&state.cwd), e))?;
+        return Ok((Some(Arc::new(journal)), state.targets));
+    }
+
+    if let Some(file) = &args.journal {
+        let cwd = env::current_dir().map_err(|e| format!("Cannot get current directory: {}", e))?;
+        let journal = Journal::create(file, &cwd, &args.paths).map_err(|e| {
+            if e.kind() == io::ErrorKind::AlreadyExists {
+                format!(
+                    "{}; continue it with --resume={}, or delete it",
+                    e,
+                    quote::quote(file)
+                )
+            } else {
+                e.to_string()
+            }
+        })?;
+        return Ok((Some(Arc::new(journal)), args.paths.clone()));
+    }
+
+    Ok((None, args.paths.clone()))
+}
+
+/// Open the --events stream, `-` being stdout
+fn open_events(args: &RmxArgs) -> Result<Option<Arc<EventSink>>, String> {
+    let file = match &args.events {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let sink = if events_to_stdout(args) {
+        EventSink::new(io::stdout())
+    } else {
+        let out = File::create(file)
+            .map_err(|e| format!("Cannot create {}: {}", quote::quote(file), e))?;
+        EventSink::new(out)
+    };
+    Ok(Some(Arc::new(sink)))
+}
+
+/// `--events=-`
+fn events_to_stdout(args: &RmxArgs) -> bool {
+    args.events
+        .as_ref()
+        .is_some_and(|file| file.as_os_str() == "-")
+}
+
+/// An option that prints to stdout, with `--output=json` or `--events=-`
+/// which need it to themselves: (option, owner of stdout)
+fn stdout_conflict(args: &RmxArgs) -> Option<(&'static str, &'static str)> {
+    let owner = if args.output == OutputFormat::Json {
+        "--output=json"
+    } else if events_to_stdout(args) {
+        "--events=-"
+    } else {
+        return None;
+    };
+
+    let option = if args.verbose {
+        "-v"
+    } else if args.format.is_some() {
+        "--format"
+    } else if args.interactive {
+        "-i"
+    } else if args.progress {
+        "--progress"
+    } else if owner == "--output=json" && events_to_stdout(args) {
+        "--events=-"
+    } else {
+        return None;
+    };
+    Some((option, owner))
+}
+
+/// Flags to pass again when resuming from the journal
+fn resume_hint(args: &RmxArgs) -> String {
+    let file = args.resume.as_ref().or(args.journal.as_ref());
+    let mut flags = String::from("-");
+    if args.recursive {
+        flags.push('r');
+    }
+    if args.force {
+        flags.push('f');
+    }
+    let file = file
+        .map(|f| QuotingStyle::ShellEscape.quote(f))
+        .unwrap_or_default();
+    if flags.len() > 1 {
+        format!("{} --resume={}", flags, file)
+    } else {
+        format!("--resume={}", file)
+    }
+}
+
+fn print_interrupted(signal: i32, incomplete: &[&PathBuf]) {
+    eprintln!(
+        "{}",
+        Color::Yellow.paint_err(format_args!(
+            "rmx: interrupted by {}",
+            signals::name(signal)
+        ))
+    );
+    if !incomplete.is_empty() {
+        eprintln!(
+            "{}",
+            Color::Yellow.paint_err(format_args!("Not completed ({}):", incomplete.len()))
+        );
+        for path in incomplete {
+            eprintln!("  {}", quote::quote(path));
+        }
+    }
+}
+
+/// Start the live progress line when asked for and stdout is a terminal
+fn start_progress(
+    args: &RmxArgs,
+    paths: &[PathBuf],
+    live: Arc<AtomicStats>,
+) -> Option<ProgressDisplay> {
+    // Per-entry output and prompts would fight with the redrawn line
+    if !args.progress
+        || args.verbose
+        || args.format.is_some()
+        || args.interactive
+        || !ProgressDisplay::available()
+    {
+        return None;
+    }
+
+    let total = if args.no_prescan {
+        None
+    } else {
+        let mut total = DeleteStats::new();
+        for path in paths {
+            total.merge(scan_tree(path));
+        }
+        Some(total)
+    };
 
-    for path_str in &args.paths {
-        let path = Path::new(path_str);
+    Some(ProgressDisplay::start(live, total))
+}
+
+/// Rename every target to a tombstone and leave the unlinking to a worker
+fn execute_background(args: &RmxArgs) {
+    if let Err(e) = background::prepare() {
+        eprintln!("{}", Color::Red.paint_err(format_args!("rmx: {}", e)));
+        std::process::exit(1);
+    }
+
+    audit::start(audit::Mode::Background, &args.paths);
+
+    let mut tombstones = Vec::new();
+    let mut queued: Vec<(&PathBuf, DeleteStats)> = Vec::new();
+    let mut failed = 0;
+
+    for path in &args.paths {
+        let is_dir = match path.symlink_metadata() {
+            Ok(metadata) => metadata.is_dir(),
+            Err(_) => {
+                if !args.force {
+                    eprintln!(
+                        "{}",
+                        Color::Red.paint_err(format_args!(
+                            "rmx: cannot remove {}: No such file or directory",
+                            quote::quote(path)
+                        ))
+                    );
+                    failed += 1;
+                }
+                continue;
+            }
+        };
 
-        // Check if path exists
-        if !path.exists() {
+        if is_dir && !args.recursive {
+            eprintln!(
+                "{}",
+                Color::Red.paint_err(format_args!(
+                    "rmx: cannot remove {}: Is a directory (use -r to remove directories)",
+                    quote::quote(path)
+                ))
+            );
             if !args.force {
-                eprintln!(
-                    "{}rmx: cannot remove '{}': No such file or directory{}",
-                    color::Fg(color::LightRed),
-                    path_str,
-                    style::Reset
-                );
-                std::process::exit(1);
+                failed += 1;
             }
             continue;
         }
 
-        // Handle based on file type and flags
-        let result = if path.is_dir() {
-            handle_directory(path, args)
-        } else {
-            handle_file(path, args)
-        };
+        if args.interactive && !prompt_user(path, is_dir) {
+            continue;
+        }
 
-        match result {
-            Ok(stats) => total_stats.merge(stats),
+        match background::bury(path) {
+            Ok(tombstone) => {
+                if args.verbose {
+                    println!(
+                        "moved {} to {}",
+                        quote::quote(path),
+                        quote::quote(&tombstone)
+                    );
+                }
+                tombstones.push(tombstone);
+                queued.push((path, DeleteStats::new()));
+            }
             Err(e) => {
-                eprintln!("{}{}{}", color::Fg(color::LightRed), e, style::Reset);
+                eprintln!("{}", Color::Red.paint_err(e));
                 if !args.force {
-                    std::process::exit(1);
+                    failed += 1;
                 }
             }
         }
     }
 
-    let elapsed_time = start_time.elapsed();
+    if !tombstones.is_empty() {
+        if let Err(e) = background::register(&tombstones).and_then(|_| background::spawn_worker()) {
+            eprintln!("{}", Color::Red.paint_err(e));
+            std::process::exit(1);
+        }
+        if args.summary != Summary::None {
+            println!(
+                "{}",
+                Color::Green.paint(format_args!(
+                    "{}Queued for background deletion: {} targets",
+                    Mark::Ok,
+                    tombstones.len()
+                ))
+            );
+        }
+    }
 
-    // Print summary
-    print_summary(&total_stats, elapsed_time, args.verbose);
+    // Only the queueing is recorded; the worker deletes later
+    audit::finish(audit::Mode::Background, &queued, &DeleteStats::new(), None);
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
 }
 
-fn handle_file(path: &Path, args: &RmxArgs) -> Result<DeleteStats, String> {
+fn handle_file(path: &Path, args: &RmxArgs, opts: &RemoveOptions) -> Result<DeleteStats, RmxError> {
     if args.interactive {
-        remove_file_interactive(path, args.verbose, args.force)
+        remove_file_interactive(path, opts)
     } else {
-        remove_file(path, args.verbose, args.force)
+        remove_file(path, opts)
     }
 }
 
-fn handle_directory(path: &Path, args: &RmxArgs) -> Result<DeleteStats, String> {
+fn handle_directory(
+    path: &Path,
+    args: &RmxArgs,
+    opts: &RemoveOptions,
+) -> Result<DeleteStats, RmxError> {
     if args.recursive {
         // Recursive deletion
         if args.interactive {
-            remove_directory_interactive(path, args.verbose, args.force)
+            remove_directory_interactive(path, opts)
         } else {
-            let mut stats = remove_directory_recursive(path, args.verbose, args.force)?;
-            // Remove the directory itself
-            match std::fs::remove_dir(path) {
-                Ok(_) => {
-                    stats.dirs_deleted += 1;
-                    if args.verbose {
-                        println!("removed directory '{}'", path.display());
-                    }
-                    Ok(stats)
-                }
-                Err(e) => {
-                    if args.force {
-                        Ok(stats)
-                    } else {
-                        Err(format!(
-                            "Cannot remove directory '{}': {}",
-                            path.display(),
-                            e
-                        ))
-                    }
-                }
-            }
+            remove_directory_tree(path, opts)
         }
     } else if args.dir {
         // Remove empty directory
-        remove_empty_directory(path, args.verbose, args.force)
+        remove_empty_directory(path, opts)
+    } else {
+        Err(RmxError::from_errno(Operation::Remove, path, libc::EISDIR))
+    }
+}
+
+/// CLI wording for library errors
+fn render_error(e: &RmxError) -> String {
+    if e.operation() == Operation::Remove && e.raw_os_error() == Some(libc::EISDIR) {
+        format!("rmx: {} (use -r to remove directories)", e)
     } else {
-        Err(format!(
-            "rmx: cannot remove '{}': Is a directory (use -r to remove directories)",
-            path.display()
+        format!("rmx: {}", e)
+    }
+}
+
+fn print_summary(stats: &DeleteStats, elapsed_time: std::time::Duration) {
+    let failed = stats.failed_count();
+    // Always show summary if anything was deleted or went wrong
+    if stats.files_deleted == 0 && stats.dirs_deleted == 0 && failed == 0 && stats.skipped == 0 {
+        return;
+    }
+
+    println!(
+        "{}",
+        Color::Green.paint(format_args!(
+            "{}Deleted: {} files, {} directories{}",
+            Mark::Ok,
+            stats.files_deleted,
+            stats.dirs_deleted,
+            file_breakdown(stats)
+        ))
+    );
+    println!(
+        "{}",
+        Color::Green.paint(format_args!(
+            "{}Total size: {}",
+            Mark::Ok,
+            bytes_to_readable(stats.total_size)
         ))
+    );
+    println!(
+        "{}",
+        Color::Green.paint(format_args!(
+            "{}Time taken: {}",
+            Mark::Ok,
+            output::plain(&format!("{:.2?}", elapsed_time))
+        ))
+    );
+    if stats.retries > 0 {
+        println!(
+            "{}",
+            Color::Yellow.paint(format_args!("{}Retries: {}", Mark::Retry, stats.retries))
+        );
+    }
+    if stats.skipped > 0 {
+        println!(
+            "{}",
+            Color::Yellow.paint(format_args!("{}Skipped: {}", Mark::Skip, stats.skipped))
+        );
+    }
+    if failed > 0 {
+        let by_code: Vec<String> = stats
+            .failed
+            .iter()
+            .map(|(code, count)| format!("{} {}", code, count))
+            .collect();
+        println!(
+            "{}",
+            Color::Red.paint(format_args!(
+                "{}Failed: {} ({})",
+                Mark::Fail,
+                failed,
+                by_code.join(", ")
+            ))
+        );
     }
 }
 
-fn print_summary(stats: &DeleteStats, elapsed_time: std::time::Duration, verbose: bool) {
-    // Always show summary if files were deleted (unless quiet mode is active in future)
-    if stats.files_deleted > 0 || stats.dirs_deleted > 0 {
-        if !verbose {
-            // Compact summary for non-verbose mode
-            println!(
-                "{}✓ Deleted: {} files, {} directories{}",
-                color::Fg(color::LightGreen),
-                stats.files_deleted,
-                stats.dirs_deleted,
-                style::Reset
-            );
-            println!(
-                "{}✓ Total size: {}{}",
-                color::Fg(color::LightGreen),
-                bytes_to_readable(stats.total_size),
-                style::Reset
-            );
-            println!(
-                "{}✓ Time taken: {:.2?}{}",
-                color::Fg(color::LightGreen),
-                elapsed_time,
-                style::Reset
-            );
-        } else {
-            // Verbose mode shows individual file deletions + summary
-            println!(
-                "{}✓ Deleted: {} files, {} directories{}",
-                color::Fg(color::LightGreen),
-                stats.files_deleted,
-                stats.dirs_deleted,
-                style::Reset
-            );
-            println!(
-                "{}✓ Total size: {}{}",
-                color::Fg(color::LightGreen),
-                bytes_to_readable(stats.total_size),
-                style::Reset
-            );
-            println!(
-                "{}✓ Time taken: {:.2?}{}",
-                color::Fg(color::LightGreen),
-                elapsed_time,
-                style::Reset
-            );
+/// One line per command line argument, for --summary=full
+fn print_breakdown(breakdown: &[(&PathBuf, DeleteStats)]) {
+    for (path, stats) in breakdown {
+        let mut line = format!(
+            "  {}: {} files, {} directories, {}",
+            quote::quote(path),
+            stats.files_deleted,
+            stats.dirs_deleted,
+            bytes_to_readable(stats.total_size)
+        );
+        if stats.failed_count() > 0 {
+            line.push_str(&format!(", {} failed", stats.failed_count()));
         }
+        println!("{}", line);
+    }
+}
+
+/// " (2 symlinks, 1 special)" when there are any
+fn file_breakdown(stats: &DeleteStats) -> String {
+    let mut parts = Vec::new();
+    if stats.symlinks_deleted > 0 {
+        parts.push(format!("{} symlinks", stats.symlinks_deleted));
+    }
+    if stats.special_deleted > 0 {
+        parts.push(format!("{} special", stats.special_deleted));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
     }
 }
 
 pub fn print_version() {
     println!(
-        "{}rmx v{}{}",
-        color::Fg(color::LightGreen),
-        env!("CARGO_PKG_VERSION"),
-        style::Reset
+        "{}",
+        Color::Green.paint(format_args!("rmx v{}", env!("CARGO_PKG_VERSION")))
     );
 }
 
 pub fn print_help() {
-    println!(
-        "{}rmx v{}{} - Blazing fast alternative to rm command
+    let text = format!(
+        "{} - Blazing fast alternative to rm command
 
-{}USAGE:{}
+{}
     rmx [OPTIONS] <FILE|DIRECTORY>...
 
-{}OPTIONS:{}
-    -r, -R, --recursive     Remove directories and their contents recursively
-    -f, --force             Ignore nonexistent files, never prompt
-    -i, --interactive       Prompt before every removal
-    -v, --verbose           Explain what is being done
-    -d, --dir               Remove empty directories
-
-    --version               Show version
-    --help                  Show this help message
-
-{}COMMANDS:{}
-    about                   Show program information
-    dev                     Show developer information
-    upgrade                 Upgrade to the latest version
-    check-update            Check if a new version is available
-
-{}EXAMPLES:{}
-    rmx file.txt                    Remove a single file
-    rmx file1.txt file2.txt         Remove multiple files
-    rmx -r directory/               Remove directory recursively
-    rmx -rf /tmp/test/              Force remove directory
-    rmx -i file.txt                 Interactive removal
-    rmx -v -r build/                Verbose recursive removal
-    rmx -d empty_dir/               Remove empty directory
-    rmx upgrade                     Upgrade to latest version
-
-{}PERFORMANCE:{}
+{}
+{}{}
+{}
+{}
+{}
+{}
     • 2x faster than standard rm for large directories
     • Parallel processing using Rayon
     • Optimized for both small and large files
     • Minimal memory footprint
 
-{}WARNING:{}
+{}
     This tool permanently deletes files. Use with caution!
     Always double-check the path before running.
-{}",
-        color::Fg(color::LightGreen),
-        env!("CARGO_PKG_VERSION"),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightRed),
-        style::Reset,
-        style::Reset
+",
+        Color::Green.paint(format_args!("rmx v{}", env!("CARGO_PKG_VERSION"))),
+        Color::Cyan.paint("USAGE:"),
+        Color::Cyan.paint("OPTIONS:"),
+        options_help(),
+        Color::Cyan.paint("COMMANDS:"),
+        commands_help(),
+        Color::Cyan.paint("EXAMPLES:"),
+        examples_help(),
+        Color::Cyan.paint("PERFORMANCE:"),
+        Color::Red.paint("WARNING:")
     );
+    println!("{}", output::plain(&text));
+}
+
+/// Command lines shown by `--help` and the manual page
+pub const EXAMPLES: &[(&str, &str)] = &[
+    ("rmx file.txt", "Remove a single file"),
+    ("rmx file1.txt file2.txt", "Remove multiple files"),
+    ("rmx -r directory/", "Remove directory recursively"),
+    ("rmx -rf /tmp/test/", "Force remove directory"),
+    ("rmx -i file.txt", "Interactive removal"),
+    ("rmx -v -r build/", "Verbose recursive removal"),
+    ("rmx -d empty_dir/", "Remove empty directory"),
+    ("rmx -- -file", "Remove a file whose name starts with '-'"),
+    (
+        "rmx -rf --background target/",
+        "Return at once, delete in background",
+    ),
+    ("rmx upgrade", "Upgrade to latest version"),
+    ("rmx bench --compare-rm", "Measure rmx against rm -rf"),
+];
+
+fn examples_help() -> String {
+    EXAMPLES
+        .iter()
+        .map(|(command, what)| format!("    {:<32}{}\n", command, what))
+        .collect()
+}
+
+/// OPTIONS section of the help, from the option table
+fn options_help() -> String {
+    const WIDTH: usize = 24;
+    let mut out = String::new();
+
+    for group in args::OPTION_GROUPS {
+        for spec in group.iter().filter(|spec| !spec.help.is_empty()) {
+            let mut label: Vec<String> = spec.short.iter().map(|c| format!("-{}", c)).collect();
+            label.push(match spec.value {
+                Value::None => spec.long.to_string(),
+                Value::Required(name) => format!("{}={}", spec.long, name),
+                Value::Optional(name) => format!("{}[={}]", spec.long, name),
+            });
+            let label = label.join(", ");
+
+            let mut lines = spec.help.iter();
+            if label.len() < WIDTH {
+                let first = lines.next().unwrap_or(&"");
+                out.push_str(&format!("    {:<width$}{}\n", label, first, width = WIDTH));
+            } else {
+                out.push_str(&format!("    {}\n", label));
+            }
+            for line in lines {
+                out.push_str(&format!("    {:<width$}{}\n", "", line, width = WIDTH));
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// COMMANDS section of the help, from the subcommand table
+fn commands_help() -> String {
+    const WIDTH: usize = 24;
+    const OPTIONS_WIDTH: usize = 48;
+    let mut out = String::new();
+
+    for command in args::SUBCOMMANDS.iter().filter(|c| !c.help.is_empty()) {
+        let label = format!("{} {}", command.name, command.usage);
+        let mut lines: Vec<String> = command.help.iter().map(|line| line.to_string()).collect();
+
+        // Subcommand options are listed by name only, wrapped
+        let mut current = String::new();
+        for spec in command.options {
+            let name = match spec.value {
+                Value::None => spec.long.to_string(),
+                Value::Required(value) | Value::Optional(value) => {
+                    format!("{}={}", spec.long, value)
+                }
+            };
+            if !current.is_empty() && current.len() + 1 + name.len() > OPTIONS_WIDTH {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(&name);
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            let label = if i == 0 { label.trim_end() } else { "" };
+            out.push_str(&format!("    {:<width$}{}\n", label, line, width = WIDTH));
+        }
+    }
+
+    out
 }
 
 pub fn print_about() {
-    println!(
-        "{}rmx - Blazing Fast File Deletion Tool{}
+    let text = format!(
+        "{}
 
 A high-performance alternative to 'rm' written in Rust.
 Uses parallel processing for maximum speed.
 
-{}Features:{}
+{}
   • Full rm compatibility (-r, -f, -i, -v, -d)
   • Parallel file deletion with Rayon
   • 2x faster than rm for medium-large files
@@ -239,48 +781,37 @@ Uses parallel processing for maximum speed.
   • Multiple file/directory support
   • Cross-platform (Linux & macOS)
 
-{}Performance Benchmarks:{}
+{}
   • 1K files × 10MB:  ~2x faster than rm
   • 50K small files:  ~2x faster than rm
   • Large directories: ~2x faster than rm
   • Memory usage:     50% less than rm
-{}",
-        color::Fg(color::LightGreen),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        style::Reset
+",
+        Color::Green.paint("rmx - Blazing Fast File Deletion Tool"),
+        Color::Cyan.paint("Features:"),
+        Color::Cyan.paint("Performance Benchmarks:")
     );
+    println!("{}", output::plain(&text));
 }
 
 pub fn print_dev() {
     println!(
-        "{}Developer Information{}
-
-{}Author:{}       Otabek Ismoilov
-{}Email:{}        ismoilovdev@gmail.com
-{}GitHub:{}       @ismoilovdevml
-{}Repository:{}   https://github.com/ismoilovdevml/rmx
-{}License:{}      MIT
-{}Version:{}      v{}
-{}",
-        color::Fg(color::LightGreen),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        color::Fg(color::LightCyan),
-        style::Reset,
-        env!("CARGO_PKG_VERSION"),
-        style::Reset
+        "{}
+
+{}       Otabek Ismoilov
+{}        ismoilovdev@gmail.com
+{}       @ismoilovdevml
+{}   https://github.com/ismoilovdevml/rmx
+{}      MIT
+{}      v{}
+",
+        Color::Green.paint("Developer Information"),
+        Color::Cyan.paint("Author:"),
+        Color::Cyan.paint("Email:"),
+        Color::Cyan.paint("GitHub:"),
+        Color::Cyan.paint("Repository:"),
+        Color::Cyan.paint("License:"),
+        Color::Cyan.paint("Version:"),
+        env!("CARGO_PKG_VERSION")
     );
 }
@@ -0,0 +1,455 @@
+use std::ffi::OsString;
+
+use crate::args::{self, OptionSpec, Subcommand, Value};
+
+/// Shells `rmx completions` writes a script for
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// Entry point for `rmx completions SHELL`
+///
+/// The scripts are generated from the option and subcommand tables, so
+/// they always match what the parser accepts and `--help` lists.
+pub fn run(args: &[OsString]) -> Result<(), String> {
+    let shell = match args {
+        [shell] => shell.to_string_lossy(),
+        _ => {
+            return Err(format!(
+                "Usage: rmx completions SHELL (one of: {})",
+                SHELLS.join(", ")
+            ))
+        }
+    };
+
+    let script = match shell.as_ref() {
+        "bash" => bash(),
+        "zsh" => zsh(),
+        "fish" => fish(),
+        other => {
+            return Err(format!(
+                "Unknown shell: {} (expected one of: {})",
+                other,
+                SHELLS.join(", ")
+            ))
+        }
+    };
+    print!("{}", script);
+    Ok(())
+}
+
+/// What to offer as the value of an option
+enum Completion {
+    Words(Vec<&'static str>),
+    Files,
+    Directories,
+    /// Free-form value such as a number or a size
+    Nothing,
+}
+
+fn value_completion(spec: &OptionSpec) -> Completion {
+    let choices = args::choices(spec.long);
+    if !choices.is_empty() {
+        return Completion::Words(choices);
+    }
+    match spec.value {
+        Value::Required("FILE") | Value::Optional("FILE") => Completion::Files,
+        Value::Required("PATH") | Value::Optional("PATH") => Completion::Directories,
+        _ => Completion::Nothing,
+    }
+}
+
+/// Options listed by `--help`
+fn listed_options() -> impl Iterator<Item = &'static OptionSpec> {
+    args::options().filter(|spec| !spec.help.is_empty())
+}
+
+/// Subcommands listed by `--help`
+fn listed_commands() -> impl Iterator<Item = &'static Subcommand> {
+    args::SUBCOMMANDS
+        .iter()
+        .filter(|command| !command.help.is_empty())
+}
+
+fn takes_arguments(command: &Subcommand) -> bool {
+    !command.options.is_empty() || !command.args.is_empty()
+}
+
+/// `-r`, `-R`, `--recursive`
+fn names(spec: &OptionSpec) -> Vec<String> {
+    let mut names: Vec<String> = spec.short.iter().map(|c| format!("-{}", c)).collect();
+    names.push(spec.long.to_string());
+    names
+}
+
+/// Help text on a single line
+fn description(help: &[&str]) -> String {
+    help.join(" ")
+}
+
+fn bash() -> String {
+    let command_names: Vec<_> = listed_commands().map(|c| c.name).collect();
+    let plain: Vec<_> = listed_commands()
+        .filter(|c| !takes_arguments(c))
+        .map(|c| c.name)
+        .collect();
+    let mut commands = format!(
+        "        {})\n            return\n            ;;\n",
+        plain.join("|")
+    );
+    for command in listed_commands() {
+        if !takes_arguments(command) {
+            continue;
+        }
+        commands.push_str(&format!("        {})\n", command.name));
+        if !command.args.is_empty() {
+            commands.push_str(&format!(
+                "            ((COMP_CWORD == 2)) && COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n",
+                command.args.join(" ")
+            ));
+        }
+        if !command.options.is_empty() {
+            commands.push_str(&bash_values(command.options, "            "));
+            commands.push_str(&format!(
+                "            _rmx_options \"{}\"\n",
+                bash_option_words(command.options)
+            ));
+        }
+        commands.push_str("            return\n            ;;\n");
+    }
+
+    let options: Vec<_> = listed_options().copied().collect();
+
+    format!(
+        r#"# bash completion for rmx, generated by `rmx completions bash`
+
+_rmx_files() {{
+    local IFS=$'\n'
+    COMPREPLY+=($(compgen -f -- "$cur"))
+    compopt -o filenames 2>/dev/null
+}}
+
+_rmx_directories() {{
+    local IFS=$'\n'
+    COMPREPLY+=($(compgen -d -- "$cur"))
+    compopt -o filenames 2>/dev/null
+}}
+
+_rmx_options() {{
+    COMPREPLY+=($(compgen -W "$1" -- "$cur"))
+    [[ ${{COMPREPLY[0]}} == *= ]] && compopt -o nospace 2>/dev/null
+}}
+
+_rmx() {{
+    local cur=${{COMP_WORDS[COMP_CWORD]}} prev= option= joined= word
+    ((COMP_CWORD > 0)) && prev=${{COMP_WORDS[COMP_CWORD-1]}}
+    # '=' splits words: --color=al arrives as "--color" "=" "al"
+    if [[ $cur == = ]]; then
+        option=$prev cur= joined=1
+    elif [[ $prev == = ]] && ((COMP_CWORD > 1)); then
+        option=${{COMP_WORDS[COMP_CWORD-2]}} joined=1
+    else
+        option=$prev
+    fi
+    COMPREPLY=()
+
+    if ((COMP_CWORD == 1)) && [[ $cur != -* ]]; then
+        COMPREPLY=($(compgen -W "{command_names}" -- "$cur"))
+        _rmx_files
+        return
+    fi
+
+    case ${{COMP_WORDS[1]}} in
+{commands}    esac
+
+    # Everything after -- is a path
+    for word in "${{COMP_WORDS[@]:1:COMP_CWORD-1}}"; do
+        if [[ $word == -- ]]; then
+            _rmx_files
+            return
+        fi
+    done
+
+{values}
+    if [[ $cur == -* ]]; then
+        _rmx_options "{option_words}"
+        return
+    fi
+    _rmx_files
+}}
+
+complete -F _rmx rmx
+"#,
+        command_names = command_names.join(" "),
+        commands = commands,
+        values = bash_values(&options, "    "),
+        option_words = bash_option_words(&options),
+    )
+}
+
+/// `case` over the option whose value is being completed
+fn bash_values(options: &[OptionSpec], indent: &str) -> String {
+    // (options, reply), options sharing a reply in a single arm
+    let mut arms: Vec<(Vec<&str>, String)> = Vec::new();
+    for spec in options.iter().filter(|spec| spec.value != Value::None) {
+        let reply = match value_completion(spec) {
+            Completion::Words(words) => format!(
+                "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return",
+                words.join(" ")
+            ),
+            Completion::Files => "_rmx_files; return".to_string(),
+            Completion::Directories => "_rmx_directories; return".to_string(),
+            Completion::Nothing => "return".to_string(),
+        };
+        let reply = match spec.value {
+            // `--name VALUE` would make VALUE a path
+            Value::Optional(_) => format!("if [[ $joined ]]; then {}; fi", reply),
+            _ => reply,
+        };
+        match arms.iter_mut().find(|(_, r)| *r == reply) {
+            Some((names, _)) => names.push(spec.long),
+            None => arms.push((vec![spec.long], reply)),
+        }
+    }
+
+    let mut out = format!("{}case $option in\n", indent);
+    for (names, reply) in arms {
+        out.push_str(&format!(
+            "{}    {}) {} ;;\n",
+            indent,
+            names.join("|"),
+            reply
+        ));
+    }
+    out.push_str(&format!("{}esac\n", indent));
+    out
+}
+
+/// Option names, with a trailing `=` on those that need a value
+fn bash_option_words(options: &[OptionSpec]) -> String {
+    let mut words = Vec::new();
+    for spec in options {
+        words.extend(spec.short.iter().map(|c| format!("-{}", c)));
+        words.push(match spec.value {
+            Value::Required(_) => format!("{}=", spec.long),
+            _ => spec.long.to_string(),
+        });
+    }
+    words.join(" ")
+}
+
+fn zsh() -> String {
+    let mut described = String::new();
+    for command in listed_commands() {
+        described.push_str(&format!(
+            "        '{}:{}'\n",
+            command.name,
+            zsh_quote(&description(command.help))
+        ));
+    }
+
+    let plain: Vec<_> = listed_commands()
+        .filter(|c| !takes_arguments(c))
+        .map(|c| c.name)
+        .collect();
+    let mut commands = format!(
+        "        {})\n            return\n            ;;\n",
+        plain.join("|")
+    );
+    for command in listed_commands() {
+        if !takes_arguments(command) {
+            continue;
+        }
+        commands.push_str(&format!("        {})\n", command.name));
+        let mut specs: Vec<_> = command.options.iter().map(zsh_spec).collect();
+        if !command.args.is_empty() {
+            specs.push(format!(
+                "'1:{}:({})'",
+                command.usage.to_lowercase(),
+                command.args.join(" ")
+            ));
+        }
+        commands.push_str("            shift words\n            ((CURRENT--))\n");
+        commands.push_str(&format!(
+            "            _arguments -S \\\n                {}\n",
+            specs.join(" \\\n                ")
+        ));
+        commands.push_str("            return\n            ;;\n");
+    }
+
+    let mut options = String::new();
+    for spec in listed_options() {
+        options.push_str(&format!("        {} \\\n", zsh_spec(spec)));
+    }
+
+    format!(
+        r#"#compdef rmx
+# zsh completion for rmx, generated by `rmx completions zsh`
+
+_rmx_commands() {{
+    local -a commands
+    commands=(
+{described}    )
+    _describe -t commands command commands
+}}
+
+_rmx() {{
+    if ((CURRENT == 2)) && [[ $PREFIX != -* ]]; then
+        _alternative 'commands:command:_rmx_commands' 'files:file:_files'
+        return
+    fi
+
+    case $words[2] in
+{commands}    esac
+
+    _arguments -s -S \
+{options}        '*:file:_files'
+}}
+
+if [[ $funcstack[1] == _rmx ]]; then
+    _rmx "$@"
+else
+    compdef _rmx rmx
+fi
+"#
+    )
+}
+
+/// `_arguments` spec of an option
+fn zsh_spec(spec: &OptionSpec) -> String {
+    let names = names(spec);
+    let names = if names.len() > 1 {
+        format!("{{{}}}", names.join(","))
+    } else {
+        names.join("")
+    };
+    let help = zsh_quote(&description(spec.help));
+
+    let (suffix, value) = match spec.value {
+        Value::None => return format!("{}'[{}]'", names, help),
+        Value::Required(value) => ("=", format!(":{}", value)),
+        Value::Optional(value) => ("=-", format!("::{}", value)),
+    };
+    let action = match value_completion(spec) {
+        Completion::Words(words) => format!("({})", words.join(" ")),
+        Completion::Files => "_files".to_string(),
+        Completion::Directories => "_files -/".to_string(),
+        Completion::Nothing => " ".to_string(),
+    };
+    format!(
+        "{}'{}[{}]{}:{}'",
+        names,
+        suffix,
+        help,
+        value.to_lowercase(),
+        action
+    )
+}
+
+/// For use inside single quotes, and inside `[...]` of a spec
+fn zsh_quote(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn fish() -> String {
+    let command_names: Vec<_> = listed_commands().map(|c| c.name).collect();
+    let mut out = String::from(
+        "# fish completion for rmx, generated by `rmx completions fish`
+
+function __rmx_first
+    test (count (commandline -opc)) -eq 1
+end
+
+function __rmx_command
+    set -l tokens (commandline -opc)
+    test (count $tokens) -gt 1; and contains -- $tokens[2] $argv
+end
+
+",
+    );
+
+    for command in listed_commands() {
+        out.push_str(&format!(
+            "complete -c rmx -n __rmx_first -a {} -d {}\n",
+            command.name,
+            fish_quote(&description(command.help))
+        ));
+    }
+    out.push('\n');
+
+    let toplevel = format!("not __rmx_command {}", command_names.join(" "));
+    for spec in listed_options() {
+        out.push_str(&fish_option(spec, &toplevel));
+    }
+    out.push('\n');
+
+    for command in listed_commands() {
+        let condition = format!("__rmx_command {}", command.name);
+        out.push_str(&format!(
+            "complete -c rmx -n {} -f\n",
+            fish_quote(&condition)
+        ));
+        for spec in command.options {
+            out.push_str(&fish_option(spec, &condition));
+        }
+        if !command.args.is_empty() {
+            out.push_str(&format!(
+                "complete -c rmx -n {} -a {}\n",
+                fish_quote(&format!(
+                    "{}; and test (count (commandline -opc)) -eq 2",
+                    condition
+                )),
+                fish_quote(&command.args.join(" "))
+            ));
+        }
+    }
+
+    out
+}
+
+/// `complete` lines for one option, offered when `condition` holds
+fn fish_option(spec: &OptionSpec, condition: &str) -> String {
+    let mut line = format!("complete -c rmx -n {}", fish_quote(condition));
+    for c in spec.short {
+        line.push_str(&format!(" -s {}", c));
+    }
+    line.push_str(&format!(" -l {}", spec.long.trim_start_matches("--")));
+
+    let completion = value_completion(spec);
+    let mut extra = String::new();
+    match (spec.value, &completion) {
+        (Value::None, _) => {}
+        (Value::Required(_), Completion::Words(words)) => {
+            line.push_str(&format!(" -x -a {}", fish_quote(&words.join(" "))));
+        }
+        (Value::Required(_), Completion::Files) => line.push_str(" -r -F"),
+        (Value::Required(_), Completion::Directories) => {
+            line.push_str(" -x -a '(__fish_complete_directories)'");
+        }
+        (Value::Required(_), Completion::Nothing) => line.push_str(" -x"),
+        // fish has no optional values: offer `--name=VALUE` as a whole word
+        (Value::Optional(_), Completion::Words(words)) => {
+            let joined: Vec<_> = words
+                .iter()
+                .map(|word| format!("{}={}", spec.long, word))
+                .collect();
+            extra = format!(
+                "complete -c rmx -n {} -f -a {}\n",
+                fish_quote(&format!(
+                    "{}; and string match -q -- '{}=*' (commandline -ct)",
+                    condition, spec.long
+                )),
+                fish_quote(&joined.join(" "))
+            );
+        }
+        (Value::Optional(_), _) => {}
+    }
+    line.push_str(&format!(" -d {}\n", fish_quote(&description(spec.help))));
+    line + &extra
+}
+
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::quote;
+
+/// What rmx was doing when an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Reading metadata (`stat`/`lstat`)
+    Access,
+    /// Unlinking a file, symlink or special file
+    Remove,
+    /// Removing a directory (`rmdir`)
+    RemoveDir,
+    /// Listing a directory
+    ReadDir,
+    /// Starting the worker threads for a directory tree
+    StartWorkers,
+    /// Creating, reading or removing a deletion journal
+    Journal,
+    /// Leaving a directory on another filesystem alone (`one_file_system`)
+    CrossDevice,
+}
+
+/// Error returned by every fallible function in `rmx_lib`
+///
+/// Carries the operation, the path and the underlying error kind and errno,
+/// so callers can tell EACCES from ENOENT or ENOTEMPTY. It is `Clone` so
+/// failures can be collected and reported after the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RmxError {
+    operation: Operation,
+    path: PathBuf,
+    kind: io::ErrorKind,
+    errno: Option<i32>,
+    description: String,
+}
+
+impl RmxError {
+    pub fn new(operation: Operation, path: &Path, err: &io::Error) -> Self {
+        RmxError {
+            operation,
+            path: path.to_path_buf(),
+            kind: err.kind(),
+            errno: err.raw_os_error(),
+            description: describe(err),
+        }
+    }
+
+    /// Build an error from a raw errno value such as `libc::EISDIR`
+    pub fn from_errno(operation: Operation, path: &Path, errno: i32) -> Self {
+        RmxError::new(operation, path, &io::Error::from_raw_os_error(errno))
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn kind(&self) -> io::ErrorKind {
+        self.kind
+    }
+
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.errno
+    }
+
+    /// Symbolic errno name such as "EACCES", used to group failures
+    pub fn code(&self) -> &'static str {
+        self.errno.map_or("other", errno_name)
+    }
+
+    /// Reason text without the path, e.g. "Permission denied"
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl fmt::Display for RmxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = quote::quote(&self.path);
+        match self.operation {
+            Operation::Access => write!(f, "cannot access {}", path)?,
+            Operation::Remove | Operation::RemoveDir => write!(f, "cannot remove {}", path)?,
+            Operation::ReadDir => write!(f, "cannot read directory {}", path)?,
+            Operation::StartWorkers => write!(f, "cannot start workers for {}", path)?,
+            Operation::Journal => write!(f, "journal {}", path)?,
+            Operation::CrossDevice => {
+                return write!(f, "skipping {}, since it's on a different device", path)
+            }
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+impl Error for RmxError {}
+
+/// `io::Error` text without the trailing " (os error N)"
+fn describe(err: &io::Error) -> String {
+    let text = err.to_string();
+    match err.raw_os_error() {
+        Some(code) => {
+            let suffix = format!(" (os error {})", code);
+            text.strip_suffix(&suffix).unwrap_or(&text).to_string()
+        }
+        None => text,
+    }
+}
+
+/// Name of the errno values a removal can run into
+fn errno_name(errno: i32) -> &'static str {
+    match errno {
+        libc::EACCES => "EACCES",
+        libc::EPERM => "EPERM",
+        libc::ENOENT => "ENOENT",
+        libc::ENOTEMPTY => "ENOTEMPTY",
+        libc::EBUSY => "EBUSY",
+        libc::ETXTBSY => "ETXTBSY",
+        libc::EROFS => "EROFS",
+        libc::EISDIR => "EISDIR",
+        libc::ENOTDIR => "ENOTDIR",
+        libc::EIO => "EIO",
+        libc::ELOOP => "ELOOP",
+        libc::ENAMETOOLONG => "ENAMETOOLONG",
+        libc::EINVAL => "EINVAL",
+        libc::EMFILE => "EMFILE",
+        libc::ENFILE => "ENFILE",
+        libc::ENOMEM => "ENOMEM",
+        _ => "other",
+    }
+}
@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::error::RmxError;
+
+/// What a removed or skipped entry was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    /// Socket, FIFO or device node
+    Special,
+}
+
+impl EntryKind {
+    pub fn of(file_type: fs::FileType) -> EntryKind {
+        if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if crate::is_special(file_type) {
+            EntryKind::Special
+        } else {
+            EntryKind::File
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Special => "special",
+        }
+    }
+}
+
+/// One action taken on one entry
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    /// `size` is the length before removal (0 for directories)
+    Removed {
+        path: &'a Path,
+        kind: EntryKind,
+        size: u64,
+    },
+    /// Left in place on purpose: a declined prompt, or a directory that
+    /// still holds something that could not be removed
+    Skipped {
+        path: &'a Path,
+        kind: EntryKind,
+    },
+    Error(&'a RmxError),
+}
+
+/// A line of the event stream
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Seconds since the Unix epoch
+    timestamp: f64,
+    event: &'static str,
+    path: Cow<'a, str>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<&'a str>,
+}
+
+/// Newline-delimited JSON stream of every action, one object per line
+///
+/// Shared by every worker through `RemoveOptions::events`; each line is
+/// written whole under a lock, so lines from parallel workers never mix.
+/// Paths that are not valid UTF-8 are converted lossily (U+FFFD).
+///
+/// ```text
+/// {"timestamp":1760000000.25,"event":"removed","path":"build/a.o","type":"file","size":1024}
+/// {"timestamp":1760000000.25,"event":"error","path":"build/x","code":"EACCES","message":"Permission denied"}
+/// {"timestamp":1760000000.26,"event":"skipped","path":"build","type":"dir"}
+/// ```
+pub struct EventSink {
+    writer: Mutex<Output>,
+}
+
+struct Output {
+    writer: Box<dyn Write + Send>,
+    /// First write error; later events are dropped
+    error: Option<io::Error>,
+}
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSink").finish_non_exhaustive()
+    }
+}
+
+impl EventSink {
+    /// Write events to `writer`, buffered
+    pub fn new(writer: impl Write + Send + 'static) -> EventSink {
+        EventSink {
+            writer: Mutex::new(Output {
+                writer: Box::new(io::BufWriter::new(writer)),
+                error: None,
+            }),
+        }
+    }
+
+    /// Append one event as a line
+    pub fn emit(&self, event: Event) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let record = match event {
+            Event::Removed { path, kind, size } => Record {
+                timestamp,
+                event: "removed",
+                path: path.to_string_lossy(),
+                kind: Some(kind.name()),
+                size: Some(size),
+                code: None,
+                message: None,
+            },
+            Event::Skipped { path, kind } => Record {
+                timestamp,
+                event: "skipped",
+                path: path.to_string_lossy(),
+                kind: Some(kind.name()),
+                size: None,
+                code: None,
+                message: None,
+            },
+            Event::Error(error) => Record {
+                timestamp,
+                event: "error",
+                path: error.path().to_string_lossy(),
+                kind: None,
+                size: None,
+                code: Some(error.code()),
+                message: Some(error.description()),
+            },
+        };
+
+        let mut line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        line.push(b'\n');
+
+        if let Ok(mut output) = self.writer.lock() {
+            if output.error.is_none() {
+                if let Err(e) = output.writer.write_all(&line) {
+                    output.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Flush buffered events; reports the first write error of the run
+    pub fn finish(&self) -> io::Result<()> {
+        let mut output = self
+            .writer
+            .lock()
+            .map_err(|_| io::Error::other("event stream poisoned"))?;
+        if let Some(e) = output.error.take() {
+            return Err(e);
+        }
+        output.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Operation;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    /// Writer whose contents stay readable after the sink takes ownership
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from_raw_os_error(libc::ENOSPC))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn lines(buffer: &Shared) -> Vec<Value> {
+        let bytes = buffer.0.lock().unwrap();
+        String::from_utf8(bytes.clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn serializes_each_event_kind() {
+        let buffer = Shared::default();
+        let sink = EventSink::new(buffer.clone());
+        let error = RmxError::from_errno(Operation::Remove, Path::new("build/x"), libc::EACCES);
+
+        sink.emit(Event::Removed {
+            path: Path::new("build/a.o"),
+            kind: EntryKind::File,
+            size: 1024,
+        });
+        sink.emit(Event::Error(&error));
+        sink.emit(Event::Skipped {
+            path: Path::new("build"),
+            kind: EntryKind::Dir,
+        });
+        sink.finish().unwrap();
+
+        let records = lines(&buffer);
+        assert_eq!(records.len(), 3);
+        for record in &records {
+            assert!(record["timestamp"].as_f64().unwrap() > 0.0);
+        }
+
+        assert_eq!(records[0]["event"], "removed");
+        assert_eq!(records[0]["path"], "build/a.o");
+        assert_eq!(records[0]["type"], "file");
+        assert_eq!(records[0]["size"], 1024);
+        assert!(records[0].get("code").is_none());
+
+        assert_eq!(records[1]["event"], "error");
+        assert_eq!(records[1]["path"], "build/x");
+        assert_eq!(records[1]["code"], "EACCES");
+        assert_eq!(records[1]["message"], "Permission denied");
+        assert!(records[1].get("type").is_none());
+        assert!(records[1].get("size").is_none());
+
+        assert_eq!(records[2]["event"], "skipped");
+        assert_eq!(records[2]["type"], "dir");
+        assert!(records[2].get("size").is_none());
+    }
+
+    #[test]
+    fn non_utf8_paths_are_lossy() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let buffer = Shared::default();
+        let sink = EventSink::new(buffer.clone());
+        sink.emit(Event::Skipped {
+            path: Path::new(OsStr::from_bytes(b"bad\xffname")),
+            kind: EntryKind::Special,
+        });
+        sink.finish().unwrap();
+
+        let records = lines(&buffer);
+        assert_eq!(records[0]["path"], "bad\u{fffd}name");
+        assert_eq!(records[0]["type"], "special");
+    }
+
+    #[test]
+    fn finish_reports_the_first_write_error() {
+        let sink = EventSink::new(Broken);
+        // Large enough to bypass the BufWriter and hit the writer at once
+        let path = "x".repeat(16 * 1024);
+        sink.emit(Event::Skipped {
+            path: Path::new(&path),
+            kind: EntryKind::File,
+        });
+        let err = sink.finish().unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOSPC));
+    }
+}
@@ -0,0 +1,326 @@
+use std::path::Path;
+
+/// Filesystem families that get their own deletion strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsKind {
+    Tmpfs,
+    Ext4,
+    Xfs,
+    Btrfs,
+    Overlay,
+    Nfs,
+    Fuse,
+    Apfs,
+    Unknown,
+}
+
+impl FsKind {
+    pub const ALL: [FsKind; 9] = [
+        FsKind::Tmpfs,
+        FsKind::Ext4,
+        FsKind::Xfs,
+        FsKind::Btrfs,
+        FsKind::Overlay,
+        FsKind::Nfs,
+        FsKind::Fuse,
+        FsKind::Apfs,
+        FsKind::Unknown,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FsKind::Tmpfs => "tmpfs",
+            FsKind::Ext4 => "ext4",
+            FsKind::Xfs => "xfs",
+            FsKind::Btrfs => "btrfs",
+            FsKind::Overlay => "overlay",
+            FsKind::Nfs => "nfs",
+            FsKind::Fuse => "fuse",
+            FsKind::Apfs => "apfs",
+            FsKind::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FsKind> {
+        FsKind::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
+/// How a directory tree is walked and unlinked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strategy {
+    /// Entries in a single directory before switching to parallel processing
+    pub parallel_threshold: usize,
+    /// Worker threads (0 = one per CPU)
+    pub threads: usize,
+    /// Minimum number of entries handed to a worker at once
+    pub batch_size: usize,
+    /// Unlink entries in inode order to keep disk access sequential
+    pub inode_order: bool,
+}
+
+/// Built-in profile table, tuned per filesystem
+const PROFILES: [(FsKind, Strategy); 9] = [
+    // Pure memory: unlink is cheap, only very large directories pay off
+    (
+        FsKind::Tmpfs,
+        Strategy {
+            parallel_threshold: 4000,
+            threads: 0,
+            batch_size: 256,
+            inode_order: false,
+        },
+    ),
+    // Inode order keeps the inode table and bitmaps walked sequentially
+    (
+        FsKind::Ext4,
+        Strategy {
+            parallel_threshold: 1000,
+            threads: 0,
+            batch_size: 64,
+            inode_order: true,
+        },
+    ),
+    (
+        FsKind::Xfs,
+        Strategy {
+            parallel_threshold: 1000,
+            threads: 0,
+            batch_size: 64,
+            inode_order: true,
+        },
+    ),
+    (
+        FsKind::Btrfs,
+        Strategy {
+            parallel_threshold: 2000,
+            threads: 0,
+            batch_size: 128,
+            inode_order: false,
+        },
+    ),
+    // Every unlink creates a whiteout in the upper layer
+    (
+        FsKind::Overlay,
+        Strategy {
+            parallel_threshold: 2000,
+            threads: 4,
+            batch_size: 64,
+            inode_order: false,
+        },
+    ),
+    // Network round trips dominate, but a few workers must not flood the server
+    (
+        FsKind::Nfs,
+        Strategy {
+            parallel_threshold: 256,
+            threads: 4,
+            batch_size: 16,
+            inode_order: false,
+        },
+    ),
+    // Userspace daemons are usually single threaded
+    (
+        FsKind::Fuse,
+        Strategy {
+            parallel_threshold: 512,
+            threads: 2,
+            batch_size: 16,
+            inode_order: false,
+        },
+    ),
+    // For macOS APFS: parallel is beneficial at 1000+ files
+    (
+        FsKind::Apfs,
+        Strategy {
+            parallel_threshold: 2000,
+            threads: 0,
+            batch_size: 1,
+            inode_order: false,
+        },
+    ),
+    (
+        FsKind::Unknown,
+        Strategy {
+            parallel_threshold: 2000,
+            threads: 0,
+            batch_size: 1,
+            inode_order: false,
+        },
+    ),
+];
+
+/// Look up the built-in strategy for a filesystem
+pub fn profile(kind: FsKind) -> Strategy {
+    PROFILES
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, strategy)| *strategy)
+        .unwrap_or(PROFILES[PROFILES.len() - 1].1)
+}
+
+/// User overrides applied on top of the detected profile
+#[derive(Debug, Clone, Default)]
+pub struct StrategyOverrides {
+    pub fs_kind: Option<FsKind>,
+    pub parallel_threshold: Option<usize>,
+    pub threads: Option<usize>,
+    pub batch_size: Option<usize>,
+}
+
+impl StrategyOverrides {
+    /// Detect the filesystem of `path` and build the final strategy
+    pub fn resolve(&self, path: &Path) -> (FsKind, Strategy) {
+        let kind = self.fs_kind.unwrap_or_else(|| detect(path));
+        let mut strategy = profile(kind);
+
+        if let Some(threshold) = self.parallel_threshold {
+            strategy.parallel_threshold = threshold;
+        }
+        if let Some(threads) = self.threads {
+            strategy.threads = threads;
+        }
+        if let Some(batch_size) = self.batch_size {
+            strategy.batch_size = batch_size.max(1);
+        }
+
+        (kind, strategy)
+    }
+}
+
+/// Detect the filesystem type a path lives on
+#[cfg(target_os = "linux")]
+#[allow(clippy::unnecessary_cast)]
+pub fn detect(path: &Path) -> FsKind {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return FsKind::Unknown,
+    };
+
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut buf) } != 0 {
+        return FsKind::Unknown;
+    }
+
+    kind_from_magic(buf.f_type as i64)
+}
+
+/// Map a statfs `f_type` magic number to a filesystem family
+#[cfg(target_os = "linux")]
+fn kind_from_magic(magic: i64) -> FsKind {
+    const TMPFS_MAGIC: i64 = 0x0102_1994;
+    // Shared by ext2, ext3 and ext4
+    const EXT4_SUPER_MAGIC: i64 = 0xEF53;
+    const XFS_SUPER_MAGIC: i64 = 0x5846_5342;
+    const BTRFS_SUPER_MAGIC: i64 = 0x9123_683E;
+    const OVERLAYFS_SUPER_MAGIC: i64 = 0x794C_7630;
+    const NFS_SUPER_MAGIC: i64 = 0x6969;
+    const FUSE_SUPER_MAGIC: i64 = 0x6573_5546;
+
+    match magic {
+        TMPFS_MAGIC => FsKind::Tmpfs,
+        EXT4_SUPER_MAGIC => FsKind::Ext4,
+        XFS_SUPER_MAGIC => FsKind::Xfs,
+        BTRFS_SUPER_MAGIC => FsKind::Btrfs,
+        OVERLAYFS_SUPER_MAGIC => FsKind::Overlay,
+        NFS_SUPER_MAGIC => FsKind::Nfs,
+        FUSE_SUPER_MAGIC => FsKind::Fuse,
+        _ => FsKind::Unknown,
+    }
+}
+
+/// Detect the filesystem type a path lives on
+#[cfg(target_os = "macos")]
+pub fn detect(path: &Path) -> FsKind {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return FsKind::Unknown,
+    };
+
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut buf) } != 0 {
+        return FsKind::Unknown;
+    }
+
+    let name = unsafe { CStr::from_ptr(buf.f_fstypename.as_ptr()) };
+    match name.to_bytes() {
+        b"apfs" => FsKind::Apfs,
+        b"nfs" => FsKind::Nfs,
+        b"macfuse" | b"osxfuse" | b"fusefs" => FsKind::Fuse,
+        _ => FsKind::Unknown,
+    }
+}
+
+/// Detect the filesystem type a path lives on
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn detect(_path: &Path) -> FsKind {
+    FsKind::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for kind in FsKind::ALL {
+            assert_eq!(FsKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(FsKind::from_name("ext3"), None);
+        assert_eq!(FsKind::from_name("EXT4"), None);
+    }
+
+    #[test]
+    fn every_kind_has_a_profile() {
+        for kind in FsKind::ALL {
+            assert!(PROFILES.iter().any(|(k, _)| *k == kind), "{:?}", kind);
+            assert!(profile(kind).batch_size >= 1);
+        }
+        assert!(profile(FsKind::Ext4).inode_order);
+        assert_eq!(profile(FsKind::Nfs).threads, 4);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn magic_numbers_map_to_kinds() {
+        assert_eq!(kind_from_magic(0x0102_1994), FsKind::Tmpfs);
+        assert_eq!(kind_from_magic(0xEF53), FsKind::Ext4);
+        assert_eq!(kind_from_magic(0x5846_5342), FsKind::Xfs);
+        assert_eq!(kind_from_magic(0x9123_683E), FsKind::Btrfs);
+        assert_eq!(kind_from_magic(0x794C_7630), FsKind::Overlay);
+        assert_eq!(kind_from_magic(0x6969), FsKind::Nfs);
+        assert_eq!(kind_from_magic(0x6573_5546), FsKind::Fuse);
+        assert_eq!(kind_from_magic(0), FsKind::Unknown);
+        assert_eq!(kind_from_magic(0x5346_544E), FsKind::Unknown);
+    }
+
+    #[test]
+    fn overrides_replace_profile_values() {
+        let overrides = StrategyOverrides {
+            fs_kind: Some(FsKind::Nfs),
+            parallel_threshold: Some(10),
+            threads: None,
+            batch_size: Some(0),
+        };
+        let (kind, strategy) = overrides.resolve(Path::new("/nonexistent"));
+        assert_eq!(kind, FsKind::Nfs);
+        assert_eq!(strategy.parallel_threshold, 10);
+        assert_eq!(strategy.threads, profile(FsKind::Nfs).threads);
+        assert_eq!(strategy.batch_size, 1);
+    }
+
+    #[test]
+    fn undetectable_paths_are_unknown() {
+        assert_eq!(
+            detect(Path::new("/nonexistent/rmx-fs-profile")),
+            FsKind::Unknown
+        );
+        assert_eq!(detect(Path::new("bad\0path")), FsKind::Unknown);
+    }
+}
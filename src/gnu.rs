@@ -0,0 +1,606 @@
+use std::ffi::{CString, OsString};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::args::{flag, parse_command_line, valued, OptionSpec, Value};
+use crate::audit;
+use rmx_lib::quote::{self, QuotingStyle};
+use rmx_lib::{
+    remove_directory_tree, remove_empty_directory, remove_file, AtomicStats, DeleteStats,
+    Operation, RemoveOptions, RmxError,
+};
+
+/// GNU rm's option set; its help text is `HELP`, not generated
+const OPTIONS: &[OptionSpec] = &[
+    flag("--force", &['f'], &[]),
+    flag("", &['i'], &[]),
+    flag("", &['I'], &[]),
+    valued("--interactive", Value::Optional("WHEN"), &[]),
+    flag("--one-file-system", &[], &[]),
+    flag("--no-preserve-root", &[], &[]),
+    valued("--preserve-root", Value::Optional("all"), &[]),
+    flag("--recursive", &['r', 'R'], &[]),
+    flag("--dir", &['d'], &[]),
+    flag("--verbose", &['v'], &[]),
+    flag("--help", &[], &[]),
+    flag("--version", &[], &[]),
+    valued("--compat", Value::Required("MODE"), &[]),
+];
+
+const HELP: &str = "\
+Usage: {rm} [OPTION]... [FILE]...
+Remove (unlink) the FILE(s).
+
+  -f, --force           ignore nonexistent files and arguments, never prompt
+  -i                    prompt before every removal
+  -I                    prompt once before removing more than three files, or
+                          when removing recursively; less intrusive than -i,
+                          while still giving protection against most mistakes
+      --interactive[=WHEN]  prompt according to WHEN: never, once (-I), or
+                          always (-i); without WHEN, prompt always
+      --one-file-system  when removing a hierarchy recursively, skip any
+                          directory that is on a file system different from
+                          that of the corresponding command line argument
+      --no-preserve-root  do not treat '/' specially
+      --preserve-root[=all]  do not remove '/' (default);
+                              with 'all', reject any command line argument
+                              on a separate device from its parent
+  -r, -R, --recursive   remove directories and their contents recursively
+  -d, --dir             remove empty directories
+  -v, --verbose         explain what is being done
+      --help        display this help and exit
+      --version     output version information and exit
+
+By default, rm does not remove directories.  Use the --recursive (-r or -R)
+option to remove each listed directory, too, along with all of its contents.
+
+To remove a file whose name starts with a '-', for example '-foo',
+use one of these commands:
+  {rm} -- -foo
+
+  {rm} ./-foo
+
+Note that if you use rm to remove a file, it might be possible to recover
+some of its contents, given sufficient expertise and/or time.  For greater
+assurance that the contents are truly unrecoverable, consider using shred(1).";
+
+/// When to ask before removing (GNU's RMI_*)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interactive {
+    Always,
+    /// Only for write-protected files, when stdin is a terminal
+    Sometimes,
+    Never,
+}
+
+#[derive(Debug, Clone)]
+struct GnuArgs {
+    paths: Vec<PathBuf>,
+    interactive: Interactive,
+    prompt_once: bool,
+    ignore_missing: bool,
+    recursive: bool,
+    dir: bool,
+    verbose: bool,
+    one_file_system: bool,
+    preserve_root: bool,
+    preserve_all_root: bool,
+    help: bool,
+    version: bool,
+}
+
+impl Default for GnuArgs {
+    fn default() -> Self {
+        GnuArgs {
+            paths: Vec::new(),
+            interactive: Interactive::Sometimes,
+            prompt_once: false,
+            ignore_missing: false,
+            recursive: false,
+            dir: false,
+            verbose: false,
+            one_file_system: false,
+            preserve_root: true,
+            preserve_all_root: false,
+            help: false,
+            version: false,
+        }
+    }
+}
+
+/// Name in messages: argv[0] when run as `rm`, like GNU rm does
+static PROGRAM: OnceLock<String> = OnceLock::new();
+
+fn program() -> &'static str {
+    PROGRAM.get().map_or("rm", String::as_str)
+}
+
+/// Whether to behave like GNU rm: run as `rm`, or given `--compat=gnu`
+pub fn requested(args: &[OsString]) -> bool {
+    let invoked_as_rm = args
+        .first()
+        .and_then(|argv0| Path::new(argv0).file_name())
+        .is_some_and(|name| name == "rm");
+
+    // Past `--`, "--compat" and "gnu" are file names
+    let options: Vec<_> = args.iter().skip(1).take_while(|arg| *arg != "--").collect();
+    invoked_as_rm
+        || options.iter().any(|arg| *arg == "--compat=gnu")
+        || options
+            .windows(2)
+            .any(|pair| pair[0] == "--compat" && pair[1] == "gnu")
+}
+
+/// Run as a drop-in GNU rm: same options, messages and exit status, no
+/// summary or colors, with the rmx engine doing the deletion
+///
+/// Runs that may prompt (`-i`, or a terminal on stdin without `-f`) walk
+/// the tree sequentially so every entry can be asked about in order.
+pub fn run(args: &[OsString]) -> ! {
+    if let Some(argv0) = args
+        .first()
+        .filter(|a| Path::new(a).file_name() == Some("rm".as_ref()))
+    {
+        let _ = PROGRAM.set(argv0.to_string_lossy().into_owned());
+    }
+    // Die quietly on a closed pipe like any C program, not with an EPIPE panic
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+
+    let gnu = match parse(args) {
+        Ok(gnu) => gnu,
+        Err(e) => usage_error(&e),
+    };
+
+    if gnu.help {
+        println!("{}", HELP.replace("{rm}", program()));
+        process::exit(0);
+    }
+    if gnu.version {
+        println!("rm (rmx) {}", env!("CARGO_PKG_VERSION"));
+        process::exit(0);
+    }
+    if gnu.paths.is_empty() {
+        if gnu.ignore_missing {
+            process::exit(0);
+        }
+        usage_error("missing operand");
+    }
+
+    quote::set_style(QuotingStyle::ShellEscape);
+
+    if gnu.prompt_once && (gnu.recursive || gnu.paths.len() > 3) {
+        let count = gnu.paths.len();
+        let question = format!(
+            "remove {} argument{}{}? ",
+            count,
+            if count == 1 { "" } else { "s" },
+            if gnu.recursive { " recursively" } else { "" }
+        );
+        if !confirm(&question) {
+            process::exit(0);
+        }
+    }
+
+    audit::start(audit::Mode::Gnu, &gnu.paths);
+
+    // Counts what each operand removed, for the audit log
+    let live = Arc::new(AtomicStats::new());
+    let opts = RemoveOptions {
+        verbose: gnu.verbose,
+        one_file_system: gnu.one_file_system,
+        progress: Some(Arc::clone(&live)),
+        ..RemoveOptions::default()
+    };
+
+    let mut ok = true;
+    let mut targets = Vec::new();
+    let mut total = DeleteStats::new();
+    for path in &gnu.paths {
+        let before = live.to_delete_stats();
+        let removed = remove_operand(path, &gnu, &opts);
+        ok &= removed;
+
+        let mut stats = removed_since(&before, &live.to_delete_stats());
+        let failures = FAILURES
+            .lock()
+            .map(|mut f| f.split_off(0))
+            .unwrap_or_default();
+        for e in failures {
+            stats.record_failure(e, false);
+        }
+        // Refused without an error: '.', '/' and the like
+        if !removed && stats.failed_count() == 0 {
+            stats.skipped += 1;
+        }
+        total.merge(stats.clone());
+        targets.push((path, stats));
+    }
+    audit::finish(audit::Mode::Gnu, &targets, &total, None);
+
+    process::exit(if ok { 0 } else { 1 });
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}: {}", program(), message);
+    eprintln!("Try '{} --help' for more information.", program());
+    process::exit(1);
+}
+
+fn parse(args: &[OsString]) -> Result<GnuArgs, String> {
+    let mut gnu = GnuArgs::default();
+    let mut paths = Vec::new();
+    let specs: Vec<_> = OPTIONS.iter().collect();
+
+    parse_command_line(
+        args,
+        &specs,
+        true,
+        |spec, value| {
+            let value = value.map(|v| v.to_string_lossy());
+            match (spec.long, spec.short, value.as_deref()) {
+                ("--force", ..) => {
+                    gnu.interactive = Interactive::Never;
+                    gnu.ignore_missing = true;
+                    gnu.prompt_once = false;
+                }
+                ("", ['i'], _) | ("--interactive", _, None | Some("always" | "yes")) => {
+                    gnu.interactive = Interactive::Always;
+                    gnu.ignore_missing = false;
+                    gnu.prompt_once = false;
+                }
+                ("", ['I'], _) | ("--interactive", _, Some("once")) => {
+                    gnu.interactive = Interactive::Sometimes;
+                    gnu.ignore_missing = false;
+                    gnu.prompt_once = true;
+                }
+                ("--interactive", _, Some("never" | "no" | "none")) => {
+                    gnu.interactive = Interactive::Never;
+                    gnu.prompt_once = false;
+                }
+                ("--interactive", _, Some(other)) => {
+                    return Err(format!(
+                        "invalid argument '{}' for '--interactive'\n\
+                         Valid arguments are:\n  - 'never', 'no', 'none'\n  \
+                         - 'once'\n  - 'always', 'yes'",
+                        other
+                    ))
+                }
+                ("--one-file-system", ..) => gnu.one_file_system = true,
+                ("--no-preserve-root", ..) => gnu.preserve_root = false,
+                ("--preserve-root", _, None) => gnu.preserve_root = true,
+                ("--preserve-root", _, Some("all")) => {
+                    gnu.preserve_root = true;
+                    gnu.preserve_all_root = true;
+                }
+                ("--preserve-root", _, Some(other)) => {
+                    eprintln!(
+                        "{}: unrecognized --preserve-root argument: {}",
+                        program(),
+                        quote::quote(Path::new(other))
+                    );
+                    process::exit(1);
+                }
+                ("--recursive", ..) => gnu.recursive = true,
+                ("--dir", ..) => gnu.dir = true,
+                ("--verbose", ..) => gnu.verbose = true,
+                ("--help", ..) => gnu.help = true,
+                ("--version", ..) => gnu.version = true,
+                ("--compat", _, Some("gnu")) => {}
+                ("--compat", _, Some(other)) => {
+                    return Err(format!("unknown compatibility mode '{}'", other))
+                }
+                _ => unreachable!("unhandled option {}", spec.long),
+            }
+            Ok(())
+        },
+        &mut paths,
+    )?;
+
+    gnu.paths = paths;
+    Ok(gnu)
+}
+
+/// Remove one command line argument, reporting failures; false if any
+fn remove_operand(path: &Path, gnu: &GnuArgs, opts: &RemoveOptions) -> bool {
+    let metadata = match path.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(e) if gnu.ignore_missing && e.kind() == io::ErrorKind::NotFound => return true,
+        Err(e) => return report(&RmxError::new(Operation::Remove, path, &e)),
+    };
+
+    if !metadata.is_dir() {
+        if should_prompt(path, &metadata, gnu) && !confirm_removal(path, &metadata) {
+            return true;
+        }
+        return match remove_file(path, opts) {
+            Ok(_) => true,
+            Err(e) => report(&e),
+        };
+    }
+
+    if !gnu.recursive {
+        if !gnu.dir {
+            return report(&RmxError::from_errno(Operation::Remove, path, libc::EISDIR));
+        }
+        if should_prompt(path, &metadata, gnu) && !confirm_removal(path, &metadata) {
+            return true;
+        }
+        return match remove_empty_directory(path, opts) {
+            Ok(stats) => stats.errors.first().is_none_or(report),
+            Err(e) => report(&e),
+        };
+    }
+
+    if is_dot_or_dotdot(path) {
+        eprintln!(
+            "{}: refusing to remove '.' or '..' directory: skipping {}",
+            program(),
+            quote::quote(path)
+        );
+        return false;
+    }
+    if gnu.preserve_root && fs::canonicalize(path).is_ok_and(|p| p == Path::new("/")) {
+        if path == Path::new("/") {
+            eprintln!(
+                "{}: it is dangerous to operate recursively on '/'",
+                program()
+            );
+        } else {
+            eprintln!(
+                "{}: it is dangerous to operate recursively on {} (same as '/')",
+                program(),
+                quote::quote(path)
+            );
+        }
+        eprintln!(
+            "{}: use --no-preserve-root to override this failsafe",
+            program()
+        );
+        return false;
+    }
+    if gnu.preserve_all_root && on_other_device_than_parent(path, &metadata) {
+        eprintln!(
+            "{}: skipping {}, since it's on a different device",
+            program(),
+            quote::quote(path)
+        );
+        eprintln!("{}: and --preserve-root=all is in effect", program());
+        return false;
+    }
+
+    // Write-protected entries inside the tree are asked about too
+    if gnu.interactive == Interactive::Always
+        || (gnu.interactive == Interactive::Sometimes && io::stdin().is_terminal())
+    {
+        let mut ok = true;
+        remove_with_prompts(path, &metadata, metadata.dev(), gnu, opts, &mut ok);
+        return ok;
+    }
+
+    match remove_directory_tree(path, opts) {
+        Ok(stats) => {
+            let mut ok = true;
+            for e in &stats.errors {
+                if !(gnu.ignore_missing && e.kind() == io::ErrorKind::NotFound) {
+                    ok = report(e);
+                }
+            }
+            ok
+        }
+        Err(e) => report(&e),
+    }
+}
+
+/// Walk depth first, asking about the entries `should_prompt` picks (all of
+/// them with `-i`); returns whether `path` is gone
+///
+/// A directory is only offered for removal when everything in it was removed.
+fn remove_with_prompts(
+    path: &Path,
+    metadata: &fs::Metadata,
+    root_dev: u64,
+    gnu: &GnuArgs,
+    opts: &RemoveOptions,
+    ok: &mut bool,
+) -> bool {
+    if !metadata.is_dir() {
+        if should_prompt(path, metadata, gnu) && !confirm_removal(path, metadata) {
+            return false;
+        }
+        return match remove_file(path, opts) {
+            Ok(_) => true,
+            Err(e) => {
+                *ok = report(&e);
+                false
+            }
+        };
+    }
+
+    if gnu.one_file_system && metadata.dev() != root_dev {
+        *ok = report(&RmxError::from_errno(
+            Operation::CrossDevice,
+            path,
+            libc::EXDEV,
+        ));
+        return false;
+    }
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).collect::<Vec<_>>(),
+        Err(e) => {
+            *ok = report(&RmxError::new(Operation::ReadDir, path, &e));
+            return false;
+        }
+    };
+
+    if !entries.is_empty() {
+        let question = format!(
+            "descend into {}directory {}? ",
+            write_protected(path, metadata),
+            quote::quote(path)
+        );
+        if should_prompt(path, metadata, gnu) && !confirm(&question) {
+            return false;
+        }
+
+        let mut all_removed = true;
+        for entry in entries {
+            let child = entry.path();
+            match child.symlink_metadata() {
+                Ok(child_metadata) => {
+                    all_removed &=
+                        remove_with_prompts(&child, &child_metadata, root_dev, gnu, opts, ok);
+                }
+                Err(e) => {
+                    *ok = report(&RmxError::new(Operation::Access, &child, &e));
+                    all_removed = false;
+                }
+            }
+        }
+        if !all_removed {
+            return false;
+        }
+    }
+
+    if should_prompt(path, metadata, gnu) && !confirm_removal(path, metadata) {
+        return false;
+    }
+    match remove_empty_directory(path, opts) {
+        Ok(stats) if stats.errors.is_empty() => true,
+        Ok(stats) => {
+            for e in &stats.errors {
+                *ok = report(e);
+            }
+            false
+        }
+        Err(e) => {
+            *ok = report(&e);
+            false
+        }
+    }
+}
+
+/// Errors reported for the current operand, for the audit log
+static FAILURES: Mutex<Vec<RmxError>> = Mutex::new(Vec::new());
+
+/// Print `rm: <error>`; returns false so callers can record the failure
+fn report(e: &RmxError) -> bool {
+    eprintln!("{}: {}", program(), e);
+    if let Ok(mut failures) = FAILURES.lock() {
+        failures.push(e.clone());
+    }
+    false
+}
+
+/// What was removed between two snapshots of the live counters
+fn removed_since(before: &DeleteStats, after: &DeleteStats) -> DeleteStats {
+    DeleteStats {
+        files_deleted: after.files_deleted - before.files_deleted,
+        dirs_deleted: after.dirs_deleted - before.dirs_deleted,
+        total_size: after.total_size - before.total_size,
+        symlinks_deleted: after.symlinks_deleted - before.symlinks_deleted,
+        special_deleted: after.special_deleted - before.special_deleted,
+        ..DeleteStats::new()
+    }
+}
+
+fn should_prompt(path: &Path, metadata: &fs::Metadata, gnu: &GnuArgs) -> bool {
+    match gnu.interactive {
+        Interactive::Always => true,
+        Interactive::Never => false,
+        Interactive::Sometimes => {
+            !metadata.file_type().is_symlink()
+                && io::stdin().is_terminal()
+                && !write_protected(path, metadata).is_empty()
+        }
+    }
+}
+
+/// "remove write-protected regular file 'x'? " and the like
+fn confirm_removal(path: &Path, metadata: &fs::Metadata) -> bool {
+    let question = format!(
+        "remove {}{} {}? ",
+        write_protected(path, metadata),
+        file_type_name(metadata),
+        quote::quote(path)
+    );
+    confirm(&question)
+}
+
+/// Ask on stderr like GNU rm; anything starting with 'y' is a yes
+fn confirm(question: &str) -> bool {
+    eprint!("{}: {}", program(), question);
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => answer.trim_start().starts_with(['y', 'Y']),
+        Err(_) => false,
+    }
+}
+
+fn write_protected(path: &Path, metadata: &fs::Metadata) -> &'static str {
+    if metadata.file_type().is_symlink() {
+        return "";
+    }
+    let writable = CString::new(path.as_os_str().as_bytes())
+        .map(|c_path| unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } == 0)
+        .unwrap_or(true);
+    if writable {
+        ""
+    } else {
+        "write-protected "
+    }
+}
+
+fn file_type_name(metadata: &fs::Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        "symbolic link"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_file() {
+        if metadata.len() == 0 {
+            "regular empty file"
+        } else {
+            "regular file"
+        }
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() {
+        "block special file"
+    } else if file_type.is_char_device() {
+        "character special file"
+    } else {
+        "file"
+    }
+}
+
+/// Last component, as given, is `.` or `..` (`Path` would normalize `.` away)
+fn is_dot_or_dotdot(path: &Path) -> bool {
+    let bytes = path.as_os_str().as_bytes();
+    let trimmed = match bytes.iter().rposition(|&b| b != b'/') {
+        Some(end) => &bytes[..=end],
+        None => return false,
+    };
+    let last = match trimmed.iter().rposition(|&b| b == b'/') {
+        Some(slash) => &trimmed[slash + 1..],
+        None => trimmed,
+    };
+    matches!(last, b"." | b"..")
+}
+
+fn on_other_device_than_parent(path: &Path, metadata: &fs::Metadata) -> bool {
+    fs::metadata(path.join(".."))
+        .map(|parent| parent.dev() != metadata.dev())
+        .unwrap_or(false)
+}
@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{Operation, RmxError};
+
+/// First line of every journal; bump the version when the format changes
+const HEADER: &str = "rmx-journal";
+const VERSION: u32 = 1;
+
+/// Flush completed directories to disk every this many records
+const SYNC_EVERY: usize = 1000;
+
+/// On-disk record of a deletion, so an interrupted run can be resumed
+///
+/// Format (one record per line, paths escaped):
+///
+/// ```text
+/// rmx-journal 1
+/// cwd /home/user/project
+/// target build
+/// done build/cache/a
+/// ```
+///
+/// A directory is recorded as `done` once its whole subtree was processed;
+/// a resumed run does not descend into it again.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    writer: Mutex<Writer>,
+    done: HashSet<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Writer {
+    file: File,
+    unsynced: usize,
+}
+
+/// What a journal recorded about the interrupted run
+#[derive(Debug, Clone)]
+pub struct JournalState {
+    pub cwd: PathBuf,
+    pub targets: Vec<PathBuf>,
+}
+
+impl Journal {
+    /// Start a new journal for `targets`, relative to `cwd`
+    ///
+    /// A non-empty file at `path` is refused with `AlreadyExists`: it may
+    /// hold an unfinished run that `resume` can still continue.
+    pub fn create(path: &Path, cwd: &Path, targets: &[PathBuf]) -> Result<Journal, RmxError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| RmxError::new(Operation::Journal, path, &e))?;
+        let len = file
+            .metadata()
+            .map_err(|e| RmxError::new(Operation::Journal, path, &e))?
+            .len();
+        if len > 0 {
+            let e = io::Error::new(io::ErrorKind::AlreadyExists, "holds an unfinished run");
+            return Err(RmxError::new(Operation::Journal, path, &e));
+        }
+
+        let mut contents = format!("{} {}\n", HEADER, VERSION).into_bytes();
+        push_record(&mut contents, "cwd", cwd);
+        for target in targets {
+            push_record(&mut contents, "target", target);
+        }
+        file.write_all(&contents)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| RmxError::new(Operation::Journal, path, &e))?;
+
+        Ok(Journal {
+            path: absolute(path),
+            writer: Mutex::new(Writer { file, unsynced: 0 }),
+            done: HashSet::new(),
+        })
+    }
+
+    /// Reopen an existing journal to continue where it stopped
+    pub fn resume(path: &Path) -> Result<(Journal, JournalState), RmxError> {
+        let contents = fs::read(path).map_err(|e| RmxError::new(Operation::Journal, path, &e))?;
+        let invalid = || invalid_data(path, "not an rmx journal".to_string());
+
+        // A record cut short by a crash is dropped, like it was never written
+        let complete = contents
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(&contents[..0], |end| &contents[..end]);
+
+        let mut lines = complete.split(|&b| b == b'\n');
+        let header = lines.next().ok_or_else(invalid)?;
+        let version = std::str::from_utf8(header)
+            .ok()
+            .and_then(|h| h.strip_prefix(HEADER))
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or_else(invalid)?;
+        if version != VERSION {
+            return Err(invalid_data(
+                path,
+                format!(
+                    "version {} is not supported (expected {})",
+                    version, VERSION
+                ),
+            ));
+        }
+
+        let mut state = JournalState {
+            cwd: PathBuf::from("."),
+            targets: Vec::new(),
+        };
+        let mut done = HashSet::new();
+
+        for line in lines.filter(|l| !l.is_empty()) {
+            let split = line.iter().position(|&b| b == b' ').ok_or_else(invalid)?;
+            let value = unescape(&line[split + 1..]);
+            match &line[..split] {
+                b"cwd" => state.cwd = value,
+                b"target" => state.targets.push(value),
+                b"done" => {
+                    done.insert(value);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| RmxError::new(Operation::Journal, path, &e))?;
+
+        let journal = Journal {
+            path: absolute(path),
+            writer: Mutex::new(Writer { file, unsynced: 0 }),
+            done,
+        };
+        Ok((journal, state))
+    }
+
+    /// Whether an earlier run already finished this directory
+    #[inline]
+    pub fn is_done(&self, dir: &Path) -> bool {
+        !self.done.is_empty() && self.done.contains(dir)
+    }
+
+    /// Record a directory whose subtree was fully processed
+    pub fn record_done(&self, dir: &Path) {
+        let mut line = Vec::new();
+        push_record(&mut line, "done", dir);
+
+        if let Ok(mut writer) = self.writer.lock() {
+            // A lost record only costs a rescan on resume
+            let _ = writer.file.write_all(&line);
+            writer.unsynced += 1;
+            if writer.unsynced >= SYNC_EVERY {
+                let _ = writer.file.sync_data();
+                writer.unsynced = 0;
+            }
+        }
+    }
+
+    /// Flush pending records, e.g. before exiting on a signal
+    pub fn sync(&self) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.file.sync_data();
+            writer.unsynced = 0;
+        }
+    }
+
+    /// Delete the journal after a successful run
+    pub fn finish(&self) -> Result<(), RmxError> {
+        match fs::remove_file(&self.path) {
+            Ok(_) => Ok(()),
+            // It was inside one of the deleted trees
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(RmxError::new(Operation::Journal, &self.path, &e)),
+        }
+    }
+}
+
+/// The journal is removed after the resumed run may have changed directory
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn invalid_data(path: &Path, message: String) -> RmxError {
+    RmxError::new(
+        Operation::Journal,
+        path,
+        &io::Error::new(io::ErrorKind::InvalidData, message),
+    )
+}
+
+fn push_record(out: &mut Vec<u8>, kind: &str, path: &Path) {
+    out.extend_from_slice(kind.as_bytes());
+    out.push(b' ');
+    for &b in path.as_os_str().as_bytes() {
+        match b {
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            _ => out.push(b),
+        }
+    }
+    out.push(b'\n');
+}
+
+fn unescape(escaped: &[u8]) -> PathBuf {
+    let mut raw = Vec::with_capacity(escaped.len());
+    let mut bytes = escaped.iter();
+    while let Some(&b) = bytes.next() {
+        if b == b'\\' {
+            match bytes.next() {
+                Some(b'n') => raw.push(b'\n'),
+                Some(&other) => raw.push(other),
+                None => raw.push(b'\\'),
+            }
+        } else {
+            raw.push(b);
+        }
+    }
+    PathBuf::from(OsStr::from_bytes(&raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `path` written as a record and read back
+    fn round_trip(path: &Path) -> PathBuf {
+        let mut line = Vec::new();
+        push_record(&mut line, "done", path);
+        assert_eq!(line.last(), Some(&b'\n'));
+        assert_eq!(line.iter().filter(|&&b| b == b'\n').count(), 1);
+
+        let value = line[..line.len() - 1].strip_prefix(b"done ").unwrap();
+        unescape(value)
+    }
+
+    #[test]
+    fn plain_path_is_written_as_is() {
+        let mut line = Vec::new();
+        push_record(&mut line, "target", Path::new("build/cache dir"));
+        assert_eq!(line, b"target build/cache dir\n");
+    }
+
+    #[test]
+    fn newlines_and_backslashes_round_trip() {
+        for name in [
+            "a\nb",
+            "a\\b",
+            "a\\nb",
+            "\\",
+            "trailing\\",
+            "\n\n",
+            "a\\\nb",
+        ] {
+            assert_eq!(round_trip(Path::new(name)), Path::new(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn escapes_newline_and_backslash() {
+        let mut line = Vec::new();
+        push_record(&mut line, "done", Path::new("a\\b\nc"));
+        assert_eq!(line, b"done a\\\\b\\nc\n");
+    }
+
+    #[test]
+    fn non_utf8_bytes_round_trip() {
+        let raw: &[u8] = b"caf\xe9/\xff\xfe\n\\x";
+        let path = Path::new(OsStr::from_bytes(raw));
+        assert_eq!(round_trip(path).as_os_str().as_bytes(), raw);
+    }
+
+    #[test]
+    fn unescape_keeps_a_lone_trailing_backslash() {
+        assert_eq!(unescape(b"dir\\"), Path::new("dir\\"));
+    }
+
+    #[test]
+    fn resume_reads_back_what_create_wrote() {
+        let dir = std::env::temp_dir().join(format!("rmx-journal-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("journal");
+        let odd = PathBuf::from(OsStr::from_bytes(b"odd\nname\\\xff"));
+
+        let journal =
+            Journal::create(&file, Path::new("/work dir"), std::slice::from_ref(&odd)).unwrap();
+        journal.record_done(&odd.join("sub"));
+        journal.sync();
+        drop(journal);
+
+        let (journal, state) = Journal::resume(&file).unwrap();
+        assert_eq!(state.cwd, Path::new("/work dir"));
+        assert_eq!(state.targets, vec![odd.clone()]);
+        assert!(journal.is_done(&odd.join("sub")));
+        assert!(!journal.is_done(&odd));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn create_keeps_an_unfinished_journal() {
+        let dir = std::env::temp_dir().join(format!("rmx-journal-keep-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("journal");
+        let targets = [PathBuf::from("a")];
+
+        drop(Journal::create(&file, Path::new("/"), &targets).unwrap());
+        let before = fs::read(&file).unwrap();
+        let e = Journal::create(&file, Path::new("/other"), &targets).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&file).unwrap(), before);
+
+        // An empty file holds nothing to resume
+        fs::write(&file, b"").unwrap();
+        assert!(Journal::create(&file, Path::new("/"), &targets).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,52 @@
 use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::io::{self, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub mod error;
+pub mod events;
+pub mod fs_profile;
+pub mod journal;
+pub mod quote;
+pub mod retry;
+pub mod template;
+pub mod throttle;
+pub mod truncate;
+
+pub use error::{Operation, RmxError};
+use events::{EntryKind, Event, EventSink};
+use fs_profile::{Strategy, StrategyOverrides};
+use journal::Journal;
+use retry::Retry;
+use template::EntryTemplate;
+use throttle::RateLimiter;
+use truncate::GradualTruncate;
 
 #[derive(Debug, Clone)]
 pub struct DeleteStats {
+    /// Every non-directory removed, symlinks and special files included
     pub files_deleted: usize,
     pub dirs_deleted: usize,
     pub total_size: u64,
+    /// Symlinks among `files_deleted`
+    pub symlinks_deleted: usize,
+    /// Sockets, FIFOs and device nodes among `files_deleted`
+    pub special_deleted: usize,
+    /// Removals that only succeeded after a retry (see `RemoveOptions::retry`)
+    pub retries: usize,
+    /// Entries left in place on purpose: declined prompts, and directories
+    /// that still hold something that could not be removed
+    pub skipped: usize,
+    /// Failures grouped by errno name ("EACCES", ...), counted even with `force`
+    pub failed: BTreeMap<&'static str, usize>,
+    /// Every failure met along the way (empty with `force`)
+    pub errors: Vec<RmxError>,
 }
 
 impl Default for DeleteStats {
@@ -23,6 +61,12 @@ impl DeleteStats {
             files_deleted: 0,
             dirs_deleted: 0,
             total_size: 0,
+            symlinks_deleted: 0,
+            special_deleted: 0,
+            retries: 0,
+            skipped: 0,
+            failed: BTreeMap::new(),
+            errors: Vec::new(),
         }
     }
 
@@ -30,206 +74,647 @@ impl DeleteStats {
         self.files_deleted += other.files_deleted;
         self.dirs_deleted += other.dirs_deleted;
         self.total_size += other.total_size;
+        self.symlinks_deleted += other.symlinks_deleted;
+        self.special_deleted += other.special_deleted;
+        self.retries += other.retries;
+        self.skipped += other.skipped;
+        for (code, count) in other.failed {
+            *self.failed.entry(code).or_insert(0) += count;
+        }
+        self.errors.extend(other.errors);
+    }
+
+    /// Count a failure, keeping the error itself unless it is `ignored` (`-f`)
+    pub fn record_failure(&mut self, error: RmxError, ignored: bool) {
+        *self.failed.entry(error.code()).or_insert(0) += 1;
+        if !ignored {
+            self.errors.push(error);
+        }
+    }
+
+    /// Number of failures, ignored ones included
+    pub fn failed_count(&self) -> usize {
+        self.failed.values().sum()
     }
+
+    /// Count a removed non-directory of the given type
+    fn add_file(&mut self, file_type: fs::FileType, size: u64) {
+        self.files_deleted += 1;
+        self.total_size += size;
+        if file_type.is_symlink() {
+            self.symlinks_deleted += 1;
+        } else if is_special(file_type) {
+            self.special_deleted += 1;
+        }
+    }
+}
+
+/// Socket, FIFO or device node
+pub(crate) fn is_special(file_type: fs::FileType) -> bool {
+    file_type.is_socket()
+        || file_type.is_fifo()
+        || file_type.is_block_device()
+        || file_type.is_char_device()
 }
 
 /// Atomic counters for high-performance tracking
-struct AtomicStats {
+///
+/// Shared with a caller through `RemoveOptions::progress`, they can be read
+/// from another thread while a removal is running.
+#[derive(Debug, Default)]
+pub struct AtomicStats {
     files: AtomicUsize,
     dirs: AtomicUsize,
     size: AtomicU64,
+    symlinks: AtomicUsize,
+    special: AtomicUsize,
+    retries: AtomicUsize,
+    skipped: AtomicUsize,
+    // Failures are rare, a lock is fine
+    failed: Mutex<BTreeMap<&'static str, usize>>,
+    /// Empty until a directory is entered; reused to avoid allocating
+    current_dir: Mutex<OsString>,
 }
 
 impl AtomicStats {
-    fn new() -> Self {
+    pub fn new() -> Self {
         AtomicStats {
             files: AtomicUsize::new(0),
             dirs: AtomicUsize::new(0),
             size: AtomicU64::new(0),
+            symlinks: AtomicUsize::new(0),
+            special: AtomicUsize::new(0),
+            retries: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            failed: Mutex::new(BTreeMap::new()),
+            current_dir: Mutex::new(OsString::new()),
+        }
+    }
+
+    /// Remember the directory a worker just started on
+    ///
+    /// Skipped while another worker holds the lock: the path is only a hint
+    /// for snapshots, not worth making workers wait for each other.
+    pub fn enter_dir(&self, path: &Path) {
+        if let Ok(mut current) = self.current_dir.try_lock() {
+            current.clear();
+            current.push(path.as_os_str());
         }
     }
 
-    fn to_delete_stats(&self) -> DeleteStats {
+    /// Directory recently entered by a worker
+    pub fn current_dir(&self) -> Option<PathBuf> {
+        let current = self.current_dir.lock().ok()?;
+        (!current.is_empty()).then(|| PathBuf::from(&*current))
+    }
+
+    #[inline]
+    pub fn add_file(&self, size: u64) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.size.fetch_add(size, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn add_dir(&self) {
+        self.dirs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a removed symlink (on top of `add_file`)
+    #[inline]
+    pub fn add_symlink(&self) {
+        self.symlinks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a removed socket, FIFO or device node (on top of `add_file`)
+    #[inline]
+    pub fn add_special(&self) {
+        self.special.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_retries(&self, retries: usize) {
+        self.retries.fetch_add(retries, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn add_skipped(&self) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_failure(&self, code: &'static str) {
+        if let Ok(mut failed) = self.failed.lock() {
+            *failed.entry(code).or_insert(0) += 1;
+        }
+    }
+
+    pub fn to_delete_stats(&self) -> DeleteStats {
         DeleteStats {
             files_deleted: self.files.load(Ordering::Relaxed),
             dirs_deleted: self.dirs.load(Ordering::Relaxed),
             total_size: self.size.load(Ordering::Relaxed),
+            symlinks_deleted: self.symlinks.load(Ordering::Relaxed),
+            special_deleted: self.special.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            failed: self.failed.lock().map(|f| f.clone()).unwrap_or_default(),
+            errors: Vec::new(),
         }
     }
 }
 
+/// Options shared by all removal functions
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOptions {
+    pub verbose: bool,
+    pub force: bool,
+    pub strategy: StrategyOverrides,
+    /// Unlink/rmdir budget shared by every worker
+    pub ops_limit: Option<Arc<RateLimiter>>,
+    /// Budget for bytes freed (file sizes) shared by every worker
+    pub bytes_limit: Option<Arc<RateLimiter>>,
+    /// Run workers with idle I/O priority and lowest CPU priority
+    pub low_priority: bool,
+    /// Shrink large files with `ftruncate` steps once they are unlinked
+    pub gradual_truncate: Option<GradualTruncate>,
+    /// Live counters updated alongside the per-call statistics
+    pub progress: Option<Arc<AtomicStats>>,
+    /// Raised to stop the removal; in-flight unlinks finish, nothing new starts
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Records finished directories and skips those finished by an earlier run
+    pub journal: Option<Arc<Journal>>,
+    /// Retry busy files and directories that gain entries while being removed
+    pub retry: Option<Retry>,
+    /// Leave directories on another filesystem than the top directory alone
+    pub one_file_system: bool,
+    /// Receives an event for every entry removed, skipped or failed
+    pub events: Option<Arc<EventSink>>,
+    /// Line printed for every removed entry, in place of the verbose message
+    pub format: Option<EntryTemplate>,
+}
+
+impl RemoveOptions {
+    /// Whether the removal was asked to stop
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Wait for the rate limiters before removing an entry of `size` bytes
+    #[inline]
+    fn throttle(&self, size: u64) {
+        if let Some(limit) = &self.ops_limit {
+            limit.acquire(1);
+        }
+        if let Some(limit) = &self.bytes_limit {
+            if size > 0 {
+                limit.acquire(size);
+            }
+        }
+    }
+
+    #[inline]
+    fn track_file(&self, path: &Path, file_type: fs::FileType, size: u64) {
+        if let Some(progress) = &self.progress {
+            progress.add_file(size);
+            if file_type.is_symlink() {
+                progress.add_symlink();
+            } else if is_special(file_type) {
+                progress.add_special();
+            }
+        }
+        self.emit(Event::Removed {
+            path,
+            kind: EntryKind::of(file_type),
+            size,
+        });
+    }
+
+    #[inline]
+    fn track_enter(&self, path: &Path) {
+        if let Some(progress) = &self.progress {
+            progress.enter_dir(path);
+        }
+    }
+
+    #[inline]
+    fn track_dir(&self, path: &Path) {
+        if let Some(progress) = &self.progress {
+            progress.add_dir();
+        }
+        self.emit(Event::Removed {
+            path,
+            kind: EntryKind::Dir,
+            size: 0,
+        });
+    }
+
+    /// Whether removed entries are printed (`verbose` or `format`)
+    #[inline]
+    fn announces(&self) -> bool {
+        self.verbose || self.format.is_some()
+    }
+
+    /// Print a removed entry, `depth` levels below the command line argument
+    fn announce(&self, path: &Path, metadata: &fs::Metadata, depth: usize) {
+        if let Some(format) = &self.format {
+            // One write per entry, so parallel workers never split a line
+            let _ = io::stdout()
+                .lock()
+                .write_all(&format.render(path, metadata, depth));
+        } else if self.verbose {
+            if metadata.is_dir() {
+                println!("removed directory {}", quote::quote(path));
+            } else {
+                println!("removed {}", quote::quote(path));
+            }
+        }
+    }
+
+    #[inline]
+    fn emit(&self, event: Event) {
+        if let Some(events) = &self.events {
+            events.emit(event);
+        }
+    }
+
+    /// Run `op`, retrying failures accepted by `retryable` as configured
+    ///
+    /// `before_retry` runs after each backoff and may give up by returning
+    /// false. Returns the last result and the number of retries made.
+    fn with_retries(
+        &self,
+        mut op: impl FnMut() -> io::Result<()>,
+        retryable: fn(&io::Error) -> bool,
+        mut before_retry: impl FnMut() -> bool,
+    ) -> (io::Result<()>, usize) {
+        let mut attempt = 0;
+        loop {
+            let result = op();
+            match (&result, &self.retry) {
+                (Err(e), Some(retry))
+                    if attempt < retry.attempts && retryable(e) && !self.is_cancelled() =>
+                {
+                    thread::sleep(retry.backoff(attempt));
+                    attempt += 1;
+                    if !before_retry() {
+                        return (result, attempt as usize - 1);
+                    }
+                }
+                _ => return (result, attempt as usize),
+            }
+        }
+    }
+
+    /// Unlink a file, retrying while it is busy
+    fn unlink(&self, path: &Path) -> (io::Result<()>, usize) {
+        self.with_retries(|| fs::remove_file(path), retry::is_busy, || true)
+    }
+
+    /// Throttle, then unlink a non-directory
+    ///
+    /// With gradual truncation, a large file is held open across the unlink
+    /// and only truncated once it is gone, so a failed removal leaves its
+    /// data intact.
+    fn unlink_file(&self, path: &Path, metadata: &fs::Metadata) -> (io::Result<()>, usize) {
+        let opened = self
+            .gradual_truncate
+            .as_ref()
+            .and_then(|gradual| Some((gradual, gradual.open(path, metadata)?)));
+        let (gradual, file) = match opened {
+            Some(opened) => opened,
+            None => {
+                self.throttle(metadata.len());
+                return self.unlink(path);
+            }
+        };
+
+        self.throttle(0);
+        let (result, retries) = self.unlink(path);
+        if result.is_ok() {
+            let left = gradual.shrink(
+                &file,
+                metadata.len(),
+                self.bytes_limit.as_deref(),
+                self.cancel.as_deref(),
+            );
+            if let Some(limit) = &self.bytes_limit {
+                if left > 0 {
+                    limit.acquire(left);
+                }
+            }
+        }
+        // The last blocks are freed here
+        drop(file);
+        (result, retries)
+    }
+}
+
 /// Remove a single file (optimized)
-pub fn remove_file(path: &Path, verbose: bool, force: bool) -> Result<DeleteStats, String> {
+///
+/// A symlink is removed itself, never its target.
+pub fn remove_file(path: &Path, opts: &RemoveOptions) -> Result<DeleteStats, RmxError> {
     let mut stats = DeleteStats::new();
 
     // Get file size for statistics (always track size)
-    let size = match fs::metadata(path) {
-        Ok(metadata) => metadata.len(),
-        Err(e) => {
-            if force {
-                return Ok(stats);
-            }
-            return Err(format!("Cannot access '{}': {}", path.display(), e));
-        }
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return fail(stats, RmxError::new(Operation::Access, path, &e), opts),
     };
 
+    let size = metadata.len();
+
     // Delete the file
-    match fs::remove_file(path) {
+    let (result, retries) = opts.unlink_file(path, &metadata);
+    stats.retries = retries;
+    match result {
         Ok(_) => {
-            stats.files_deleted = 1;
-            stats.total_size = size;
-            if verbose {
-                println!("removed '{}'", path.display());
-            }
+            stats.add_file(metadata.file_type(), size);
+            opts.track_file(path, metadata.file_type(), size);
+            opts.announce(path, &metadata, 0);
             Ok(stats)
         }
-        Err(e) => {
-            if force {
-                Ok(stats)
-            } else {
-                Err(format!("Cannot remove '{}': {}", path.display(), e))
-            }
-        }
+        Err(e) => fail(stats, RmxError::new(Operation::Remove, path, &e), opts),
     }
 }
 
+/// Report `error`, or with `force` only count it and carry on
+fn fail(
+    mut stats: DeleteStats,
+    error: RmxError,
+    opts: &RemoveOptions,
+) -> Result<DeleteStats, RmxError> {
+    opts.emit(Event::Error(&error));
+    if opts.force {
+        stats.record_failure(error, true);
+        Ok(stats)
+    } else {
+        Err(error)
+    }
+}
+
+/// Count `error` like the failures of the entries inside a directory, so
+/// that what was removed before it is still reported
+fn keep_going(
+    mut stats: DeleteStats,
+    error: RmxError,
+    opts: &RemoveOptions,
+) -> Result<DeleteStats, RmxError> {
+    opts.emit(Event::Error(&error));
+    stats.record_failure(error, opts.force);
+    Ok(stats)
+}
+
 /// Ultra-fast directory removal
+///
+/// Removes everything below `path`, but not `path` itself. Failures do not
+/// stop the walk: they are collected in `DeleteStats::errors` (unless
+/// `force` is set) and the ancestors of a failed entry are left in place.
+/// `Err` is only returned when the removal could not start at all.
 pub fn remove_directory_recursive(
     path: &Path,
-    verbose: bool,
-    force: bool,
-) -> Result<DeleteStats, String> {
+    opts: &RemoveOptions,
+) -> Result<DeleteStats, RmxError> {
     if !path.is_dir() {
         return Ok(DeleteStats::new());
     }
 
-    let stats = Arc::new(AtomicStats::new());
+    let (_, strategy) = opts.strategy.resolve(path);
+    let root_dev = if opts.one_file_system {
+        fs::metadata(path).ok().map(|m| m.dev())
+    } else {
+        None
+    };
+    let engine = Engine {
+        opts,
+        strategy,
+        root_dev,
+        root_depth: path.components().count(),
+        stats: Arc::new(AtomicStats::new()),
+        errors: Mutex::new(Vec::new()),
+    };
 
-    // Use optimized recursive function
-    remove_dir_recursive_fast(path, verbose, force, &stats)?;
+    // Use optimized recursive function, on a dedicated pool if the
+    // filesystem profile limits parallelism or workers must run niced
+    if strategy.threads > 0 || opts.low_priority {
+        let pool = worker_pool(strategy.threads, opts.low_priority)
+            .map_err(|e| RmxError::new(Operation::StartWorkers, path, &io::Error::other(e)))?;
+        pool.install(|| engine.remove_dir_recursive_fast(path));
+    } else {
+        engine.remove_dir_recursive_fast(path);
+    }
 
-    Ok(stats.to_delete_stats())
+    let mut stats = engine.stats.to_delete_stats();
+    stats.errors = engine
+        .errors
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner());
+    Ok(stats)
 }
 
-/// Fast recursive directory removal with adaptive parallelism
-fn remove_dir_recursive_fast(
-    path: &Path,
-    verbose: bool,
-    force: bool,
-    stats: &Arc<AtomicStats>,
-) -> Result<(), String> {
-    // Read directory entries
-    let entries: Vec<_> = match fs::read_dir(path) {
-        Ok(entries) => entries.filter_map(|e| e.ok()).collect(),
-        Err(e) => {
-            if force {
-                return Ok(());
+/// Threads and low priority
+type PoolKey = (usize, bool);
+
+/// Dedicated pools, kept for the life of the process like rayon's global
+/// pool so targets and retries share them
+static POOLS: Mutex<Vec<(PoolKey, Arc<rayon::ThreadPool>)>> = Mutex::new(Vec::new());
+
+fn worker_pool(
+    threads: usize,
+    low_priority: bool,
+) -> Result<Arc<rayon::ThreadPool>, rayon::ThreadPoolBuildError> {
+    let mut pools = POOLS.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (threads, low_priority);
+    if let Some((_, pool)) = pools.iter().find(|(k, _)| *k == key) {
+        return Ok(Arc::clone(pool));
+    }
+
+    let mut builder = rayon::ThreadPoolBuilder::new().num_threads(threads);
+    if low_priority {
+        builder = builder.start_handler(|_| throttle::lower_priority());
+    }
+    let pool = Arc::new(builder.build()?);
+    pools.push((key, Arc::clone(&pool)));
+    Ok(pool)
+}
+
+/// State shared by every worker during one recursive removal
+struct Engine<'a> {
+    opts: &'a RemoveOptions,
+    strategy: Strategy,
+    /// Device of the top directory, with `one_file_system`
+    root_dev: Option<u64>,
+    /// Components in the top directory's path, for `format` depths
+    root_depth: usize,
+    stats: Arc<AtomicStats>,
+    errors: Mutex<Vec<RmxError>>,
+}
+
+impl Engine<'_> {
+    /// Record a failure and keep going
+    fn fail(&self, error: RmxError) {
+        self.stats.add_failure(error.code());
+        self.opts.emit(Event::Error(&error));
+        if self.opts.force {
+            return;
+        }
+        if let Ok(mut errors) = self.errors.lock() {
+            errors.push(error);
+        }
+    }
+
+    /// Levels below the top directory
+    #[inline]
+    fn depth(&self, path: &Path) -> usize {
+        path.components().count().saturating_sub(self.root_depth)
+    }
+
+    /// Fast recursive directory removal with adaptive parallelism
+    ///
+    /// Returns whether every entry below `path` was removed.
+    fn remove_dir_recursive_fast(&self, path: &Path) -> bool {
+        // Finished by an earlier, interrupted run
+        if let Some(journal) = &self.opts.journal {
+            if journal.is_done(path) {
+                return true;
             }
-            return Err(format!(
-                "Failed to read directory '{}': {}",
-                path.display(),
-                e
-            ));
         }
-    };
 
-    // Adaptive parallelism threshold based on benchmarking
-    // For macOS APFS: parallel is beneficial at 1000+ files
-    const PARALLEL_THRESHOLD: usize = 2000;
-
-    if entries.len() >= PARALLEL_THRESHOLD {
-        // Use parallel processing for large directories
-        let results: Vec<Result<(), String>> = entries
-            .par_iter()
-            .map(|entry| process_entry_fast(entry, verbose, force, stats))
-            .collect();
-
-        // Check for errors
-        for result in results {
-            if let Err(e) = result {
-                if !force {
-                    return Err(e);
-                }
+        self.opts.track_enter(path);
+
+        // Read directory entries
+        let mut entries: Vec<_> = match fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).collect(),
+            Err(e) => {
+                self.fail(RmxError::new(Operation::ReadDir, path, &e));
+                return false;
             }
+        };
+
+        if self.strategy.inode_order {
+            use std::os::unix::fs::DirEntryExt;
+            entries.sort_unstable_by_key(|entry| entry.ino());
         }
-    } else {
-        // Sequential processing for small/medium directories
-        for entry in &entries {
-            if let Err(e) = process_entry_fast(entry, verbose, force, stats) {
-                if !force {
-                    return Err(e);
+
+        // Adaptive parallelism threshold comes from the filesystem profile
+        let failed = if entries.len() >= self.strategy.parallel_threshold {
+            // Use parallel processing for large directories; count() visits
+            // every entry, unlike all()/any() which would stop early
+            entries
+                .par_iter()
+                .with_min_len(self.strategy.batch_size)
+                .filter(|entry| !self.opts.is_cancelled() && !self.process_entry_fast(entry))
+                .count()
+        } else {
+            // Sequential processing for small/medium directories
+            let mut failed = 0;
+            for entry in &entries {
+                if self.opts.is_cancelled() {
+                    break;
+                }
+                if !self.process_entry_fast(entry) {
+                    failed += 1;
                 }
             }
+            failed
+        };
+
+        if let Some(journal) = &self.opts.journal {
+            // A directory still holding failed entries is not done: leaving
+            // it out lets a resumed run retry them
+            if failed == 0 && !self.opts.is_cancelled() {
+                journal.record_done(path);
+            }
         }
+
+        failed == 0
     }
 
-    Ok(())
-}
+    /// Process a single directory entry (highly optimized)
+    ///
+    /// Returns whether the entry is gone.
+    #[inline(always)]
+    fn process_entry_fast(&self, entry: &fs::DirEntry) -> bool {
+        let path = entry.path();
 
-/// Process a single directory entry (highly optimized)
-#[inline(always)]
-fn process_entry_fast(
-    entry: &fs::DirEntry,
-    verbose: bool,
-    force: bool,
-    stats: &Arc<AtomicStats>,
-) -> Result<(), String> {
-    let path = entry.path();
-
-    // Use DirEntry::metadata() which is cached on most systems
-    let metadata = match entry.metadata() {
-        Ok(m) => m,
-        Err(e) => {
-            if force {
-                return Ok(());
+        // Use DirEntry::metadata() which is cached on most systems
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(e) => {
+                self.fail(RmxError::new(Operation::Access, &path, &e));
+                return false;
+            }
+        };
+
+        if metadata.is_dir() {
+            if self.root_dev.is_some_and(|dev| dev != metadata.dev()) {
+                self.fail(RmxError::from_errno(
+                    Operation::CrossDevice,
+                    &path,
+                    libc::EXDEV,
+                ));
+                return false;
             }
-            return Err(format!("Cannot access '{}': {}", path.display(), e));
-        }
-    };
 
-    if metadata.is_dir() {
-        // Recursively delete subdirectory
-        remove_dir_recursive_fast(&path, verbose, force, stats)?;
+            // Recursively delete subdirectory; if something inside could not
+            // be removed, the directory cannot be either and is not reported
+            if !self.remove_dir_recursive_fast(&path) {
+                self.stats.add_skipped();
+                self.opts.emit(Event::Skipped {
+                    path: &path,
+                    kind: EntryKind::Dir,
+                });
+                return false;
+            }
 
-        // Remove the directory itself
-        match fs::remove_dir(&path) {
-            Ok(_) => {
-                stats.dirs.fetch_add(1, Ordering::Relaxed);
-                if verbose {
-                    println!("removed directory '{}'", path.display());
-                }
-                Ok(())
+            // Cancelled midway: the directory is not empty, leave it
+            if self.opts.is_cancelled() {
+                return false;
             }
-            Err(e) => {
-                if force {
-                    Ok(())
-                } else {
-                    Err(format!(
-                        "Cannot remove directory '{}': {}",
-                        path.display(),
-                        e
-                    ))
+
+            // Remove the directory itself; entries written meanwhile by
+            // another process are deleted before trying again
+            self.opts.throttle(0);
+            let (result, retries) = self.opts.with_retries(
+                || fs::remove_dir(&path),
+                retry::is_not_empty,
+                || self.remove_dir_recursive_fast(&path),
+            );
+            self.stats.add_retries(retries);
+            match result {
+                Ok(_) => {
+                    self.stats.add_dir();
+                    self.opts.track_dir(&path);
+                    self.opts.announce(&path, &metadata, self.depth(&path));
+                    true
                 }
-            }
-        }
-    } else {
-        // File deletion - always track size for statistics
-        let size = metadata.len();
-
-        match fs::remove_file(&path) {
-            Ok(_) => {
-                stats.files.fetch_add(1, Ordering::Relaxed);
-                stats.size.fetch_add(size, Ordering::Relaxed);
-                if verbose {
-                    println!("removed '{}'", path.display());
+                Err(e) => {
+                    self.fail(RmxError::new(Operation::RemoveDir, &path, &e));
+                    false
                 }
-                Ok(())
             }
-            Err(e) => {
-                if force {
-                    Ok(())
-                } else {
-                    Err(format!("Cannot remove '{}': {}", path.display(), e))
+        } else {
+            // File deletion - always track size for statistics
+            let size = metadata.len();
+
+            let (result, retries) = self.opts.unlink_file(&path, &metadata);
+            self.stats.add_retries(retries);
+            match result {
+                Ok(_) => {
+                    let file_type = metadata.file_type();
+                    self.stats.add_file(size);
+                    if file_type.is_symlink() {
+                        self.stats.add_symlink();
+                    } else if is_special(file_type) {
+                        self.stats.add_special();
+                    }
+                    self.opts.track_file(&path, file_type, size);
+                    self.opts.announce(&path, &metadata, self.depth(&path));
+                    true
+                }
+                Err(e) => {
+                    self.fail(RmxError::new(Operation::Remove, &path, &e));
+                    false
                 }
             }
         }
@@ -237,32 +722,26 @@ fn process_entry_fast(
 }
 
 /// Remove empty directory
-pub fn remove_empty_directory(
-    path: &Path,
-    verbose: bool,
-    force: bool,
-) -> Result<DeleteStats, String> {
+///
+/// A failed `rmdir` is counted in the returned statistics, not returned.
+pub fn remove_empty_directory(path: &Path, opts: &RemoveOptions) -> Result<DeleteStats, RmxError> {
     let mut stats = DeleteStats::new();
+    let metadata = opts
+        .announces()
+        .then(|| fs::symlink_metadata(path).ok())
+        .flatten();
 
+    opts.throttle(0);
     match fs::remove_dir(path) {
         Ok(_) => {
             stats.dirs_deleted = 1;
-            if verbose {
-                println!("removed directory '{}'", path.display());
+            opts.track_dir(path);
+            if let Some(metadata) = &metadata {
+                opts.announce(path, metadata, 0);
             }
             Ok(stats)
         }
-        Err(e) => {
-            if force {
-                Ok(stats)
-            } else {
-                Err(format!(
-                    "Cannot remove directory '{}': {}",
-                    path.display(),
-                    e
-                ))
-            }
-        }
+        Err(e) => keep_going(stats, RmxError::new(Operation::RemoveDir, path, &e), opts),
     }
 }
 
@@ -271,9 +750,9 @@ pub fn prompt_user(path: &Path, is_dir: bool) -> bool {
     use std::io::{self, Write};
 
     let prompt = if is_dir {
-        format!("remove directory '{}'? ", path.display())
+        format!("remove directory {}? ", quote::quote(path))
     } else {
-        format!("remove file '{}'? ", path.display())
+        format!("remove file {}? ", quote::quote(path))
     };
 
     print!("{}", prompt);
@@ -289,52 +768,172 @@ pub fn prompt_user(path: &Path, is_dir: bool) -> bool {
     }
 }
 
-pub fn remove_file_interactive(
-    path: &Path,
-    verbose: bool,
-    force: bool,
-) -> Result<DeleteStats, String> {
+pub fn remove_file_interactive(path: &Path, opts: &RemoveOptions) -> Result<DeleteStats, RmxError> {
     if prompt_user(path, false) {
-        remove_file(path, verbose, force)
+        remove_file(path, opts)
     } else {
-        Ok(DeleteStats::new())
+        let kind = path
+            .symlink_metadata()
+            .map_or(EntryKind::File, |m| EntryKind::of(m.file_type()));
+        opts.emit(Event::Skipped { path, kind });
+        Ok(DeleteStats {
+            skipped: 1,
+            ..DeleteStats::new()
+        })
     }
 }
 
 pub fn remove_directory_interactive(
     path: &Path,
-    verbose: bool,
-    force: bool,
-) -> Result<DeleteStats, String> {
+    opts: &RemoveOptions,
+) -> Result<DeleteStats, RmxError> {
     if prompt_user(path, true) {
-        let mut stats = remove_directory_recursive(path, verbose, force)?;
-        match fs::remove_dir(path) {
-            Ok(_) => {
-                stats.dirs_deleted += 1;
-                if verbose {
-                    println!("removed directory '{}'", path.display());
-                }
-                Ok(stats)
+        remove_directory_tree(path, opts)
+    } else {
+        opts.emit(Event::Skipped {
+            path,
+            kind: EntryKind::Dir,
+        });
+        Ok(DeleteStats {
+            skipped: 1,
+            ..DeleteStats::new()
+        })
+    }
+}
+
+/// Remove a directory together with everything below it (`rm -r`)
+///
+/// Like the failures inside it, a failed `rmdir` of `path` is counted in the
+/// returned statistics, so what was removed is still reported.
+pub fn remove_directory_tree(path: &Path, opts: &RemoveOptions) -> Result<DeleteStats, RmxError> {
+    let mut stats = remove_directory_recursive(path, opts)?;
+    if opts.is_cancelled() {
+        return Ok(stats);
+    }
+    // Not empty: the failures inside are already reported
+    if stats.failed_count() > 0 {
+        stats.skipped += 1;
+        opts.emit(Event::Skipped {
+            path,
+            kind: EntryKind::Dir,
+        });
+        return Ok(stats);
+    }
+
+    let metadata = opts
+        .announces()
+        .then(|| fs::symlink_metadata(path).ok())
+        .flatten();
+    let mut rescan = Ok(());
+    let (result, retries) = opts.with_retries(
+        || fs::remove_dir(path),
+        retry::is_not_empty,
+        || match remove_directory_recursive(path, opts) {
+            Ok(more) => {
+                let clean = more.failed_count() == 0;
+                stats.merge(more);
+                clean
             }
             Err(e) => {
-                if force {
-                    Ok(stats)
-                } else {
-                    Err(format!(
-                        "Cannot remove directory '{}': {}",
-                        path.display(),
-                        e
-                    ))
-                }
+                rescan = Err(e);
+                false
             }
+        },
+    );
+    rescan?;
+    stats.retries += retries;
+    if stats.failed_count() > 0 {
+        stats.skipped += 1;
+        opts.emit(Event::Skipped {
+            path,
+            kind: EntryKind::Dir,
+        });
+        return Ok(stats);
+    }
+
+    match result {
+        Ok(_) => {
+            stats.dirs_deleted += 1;
+            opts.track_dir(path);
+            if let Some(metadata) = &metadata {
+                opts.announce(path, metadata, 0);
+            }
+            Ok(stats)
         }
-    } else {
-        Ok(DeleteStats::new())
+        Err(e) => keep_going(stats, RmxError::new(Operation::RemoveDir, path, &e), opts),
     }
 }
 
+/// Fast pre-count of everything below `path` (files, directories, bytes)
+///
+/// The directory itself is counted too. Unreadable entries are skipped.
+pub fn scan_tree(path: &Path) -> DeleteStats {
+    let mut stats = DeleteStats::new();
+
+    let metadata = match path.symlink_metadata() {
+        Ok(m) => m,
+        Err(_) => return stats,
+    };
+    if !metadata.is_dir() {
+        stats.files_deleted = 1;
+        stats.total_size = metadata.len();
+        return stats;
+    }
+
+    stats.dirs_deleted = 1;
+    let entries: Vec<_> = match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).collect(),
+        Err(_) => return stats,
+    };
+
+    let children = entries
+        .par_iter()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => scan_tree(&entry.path()),
+            Ok(_) => DeleteStats {
+                files_deleted: 1,
+                total_size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                ..DeleteStats::new()
+            },
+            Err(_) => DeleteStats::new(),
+        })
+        .reduce(DeleteStats::new, |mut a, b| {
+            a.merge(b);
+            a
+        });
+    stats.merge(children);
+
+    stats
+}
+
 /// Legacy function for backwards compatibility
-pub fn remove_dir_contents(path: &Path) -> Result<(usize, u64), String> {
-    let stats = remove_directory_recursive(path, false, false)?;
+pub fn remove_dir_contents(path: &Path) -> Result<(usize, u64), RmxError> {
+    let stats = remove_directory_recursive(path, &RemoveOptions::default())?;
     Ok((stats.files_deleted, stats.total_size))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_rmdir_keeps_what_was_removed() {
+        let dir = std::env::temp_dir().join(format!("rmx-tree-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a"), b"12").unwrap();
+        fs::write(dir.join("sub/b"), b"345").unwrap();
+
+        // rmdir("dir/.") fails with EINVAL once everything inside is gone
+        let stats = remove_directory_tree(&dir.join("."), &RemoveOptions::default()).unwrap();
+        assert_eq!(stats.files_deleted, 2);
+        assert_eq!(stats.dirs_deleted, 1);
+        assert_eq!(stats.total_size, 5);
+        assert_eq!(stats.failed_count(), 1);
+        assert_eq!(stats.errors[0].raw_os_error(), Some(libc::EINVAL));
+
+        let stats = remove_empty_directory(&dir.join("."), &RemoveOptions::default()).unwrap();
+        assert_eq!(stats.failed_count(), 1);
+
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -1,68 +1,117 @@
 mod args;
+mod audit;
+mod background;
+mod bench;
 mod commands;
+mod completions;
+mod gnu;
+mod manpage;
+mod output;
+mod progress;
+mod report;
+mod signals;
 mod upgrade;
 mod util;
 
 fn main() {
     let raw_args = args::parse_args();
 
+    // Drop-in replacement for GNU rm when run as `rm`
+    if gnu::requested(&raw_args) {
+        gnu::run(&raw_args);
+    }
+
+    if raw_args.len() < 2 {
+        commands::print_help();
+        std::process::exit(1);
+    }
+
     // Check for special commands first
-    if raw_args.len() >= 2 {
-        let first_arg = &raw_args[1];
+    let first_arg = raw_args[1].to_str().unwrap_or_default();
 
-        match first_arg.as_str() {
-            "--version" | "-v" => {
-                commands::print_version();
-                return;
-            }
-            "--help" | "-h" | "help" => {
-                commands::print_help();
-                return;
-            }
-            "version" => {
-                commands::print_version();
-                return;
+    match first_arg {
+        "help" => {
+            commands::print_help();
+            return;
+        }
+        "version" => {
+            commands::print_version();
+            return;
+        }
+        "about" => {
+            commands::print_about();
+            return;
+        }
+        "dev" => {
+            commands::print_dev();
+            return;
+        }
+        "upgrade" => {
+            if let Err(e) = upgrade::upgrade() {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
             }
-            "about" => {
-                commands::print_about();
-                return;
+            return;
+        }
+        "bench" => {
+            if let Err(e) = bench::run(&raw_args[1..]) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
             }
-            "dev" => {
-                commands::print_dev();
-                return;
+            return;
+        }
+        "completions" => {
+            if let Err(e) = completions::run(&raw_args[2..]) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
             }
-            "upgrade" => {
-                if let Err(e) = upgrade::upgrade() {
+            return;
+        }
+        "man" => {
+            manpage::print();
+            return;
+        }
+        background::WORKER_COMMAND => {
+            background::purge_tombstones();
+            return;
+        }
+        "check-update" => {
+            match upgrade::check_for_updates() {
+                Ok(Some(_)) => {
+                    println!("Run 'rmx upgrade' to update");
+                }
+                Ok(None) => {}
+                Err(e) => {
                     eprintln!("Error: {}", e);
                     std::process::exit(1);
                 }
-                return;
             }
-            "check-update" => {
-                match upgrade::check_for_updates() {
-                    Ok(Some(_)) => {
-                        println!("Run 'rmx upgrade' to update");
-                    }
-                    Ok(None) => {}
-                    Err(e) => {
-                        eprintln!("Error: {}", e);
-                        std::process::exit(1);
-                    }
-                }
-                return;
-            }
-            _ => {}
+            return;
         }
+        _ => {}
     }
 
     // Parse flags and paths
-    match args::parse_flags(&raw_args) {
-        Some(parsed_args) => {
-            commands::execute_removal(&parsed_args);
-        }
-        None => {
-            commands::print_help();
+    let parsed_args = match args::parse_flags(&raw_args) {
+        Ok(parsed_args) => parsed_args,
+        Err(e) => {
+            eprintln!("rmx: {}", e);
+            eprintln!("Try 'rmx --help' for more information.");
             std::process::exit(1);
         }
+    };
+    output::set_color(parsed_args.color);
+    output::set_ascii(parsed_args.ascii);
+
+    if parsed_args.show_help {
+        commands::print_help();
+    } else if parsed_args.show_version {
+        commands::print_version();
+    } else if parsed_args.paths.is_empty() && parsed_args.resume.is_none() {
+        eprintln!("rmx: missing operand");
+        eprintln!("Try 'rmx --help' for more information.");
+        std::process::exit(1);
+    } else {
+        commands::execute_removal(&parsed_args);
     }
 }
@@ -0,0 +1,180 @@
+use crate::args::{self, OptionSpec, Value};
+use crate::audit;
+use crate::commands;
+
+/// Entry point for `rmx man`: the rmx(1) page, in roff
+///
+/// Options, commands and examples come from the same tables as `--help`.
+/// Install with `rmx man > /usr/share/man/man1/rmx.1`, or read it with
+/// `rmx man | man -l -`.
+pub fn print() {
+    let mut page = format!(
+        ".TH RMX 1 \"\" \"rmx {}\" \"User Commands\"\n",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    page.push_str(
+        r#".SH NAME
+rmx \- blazing fast alternative to rm
+.SH SYNOPSIS
+.B rmx
+[\fIOPTIONS\fR] \fIFILE\fR...
+.br
+.B rmx
+\fICOMMAND\fR [\fIARGS\fR]
+.SH DESCRIPTION
+.B rmx
+removes each \fIFILE\fR. Directories are only removed with \fB\-r\fR, along
+with everything below them, or with \fB\-d\fR when they are empty. Large trees
+are deleted in parallel, with a strategy picked from the type of filesystem
+they live on.
+.PP
+Options and paths may be mixed, and options taking a value accept both
+\fB\-\-name\fR=\fIVALUE\fR and \fB\-\-name\fR \fIVALUE\fR. Everything after
+\fB\-\-\fR is a path, so \fBrmx \-\- \-file\fR removes a file named
+\fI\-file\fR.
+.PP
+Run as \fBrm\fR, or with \fB\-\-compat\fR=\fIgnu\fR, rmx behaves exactly like
+GNU rm(1): same options, messages and exit status.
+"#,
+    );
+
+    page.push_str(".SH OPTIONS\n");
+    for spec in args::options().filter(|spec| !spec.help.is_empty()) {
+        page.push_str(&option_item(spec));
+    }
+
+    page.push_str(".SH COMMANDS\n");
+    for command in args::SUBCOMMANDS.iter().filter(|c| !c.help.is_empty()) {
+        page.push_str(&format!(".TP\n\\fBrmx {}\\fR", escape(command.name)));
+        if !command.usage.is_empty() {
+            page.push_str(&format!(" \\fI{}\\fR", escape(command.usage)));
+        }
+        page.push('\n');
+        page.push_str(&escape(&command.help.join(" ")));
+        page.push('\n');
+        if !command.args.is_empty() {
+            page.push_str(&format!(
+                ".br\n\\fI{}\\fR is one of: {}.\n",
+                escape(command.usage),
+                escape(&command.args.join(", "))
+            ));
+        }
+        if !command.options.is_empty() {
+            page.push_str(".RS\n");
+            for spec in command.options {
+                page.push_str(&option_item(spec));
+            }
+            page.push_str(".RE\n");
+        }
+    }
+
+    page.push_str(&format!(
+        r#".SH SAFETY
+Removal is permanent: nothing is moved to a trash.
+.IP \(bu 2
+A directory given without \fB\-r\fR or \fB\-d\fR is refused with
+"Is a directory".
+.IP \(bu 2
+A symbolic link is removed itself, never followed, whether it is given as a
+\fIFILE\fR or found inside a directory. Only a trailing slash, as in
+\fIlink/\fR, names the directory it points to.
+.IP \(bu 2
+\fB\-i\fR asks before every removal; anything but \fBy\fR or \fByes\fR keeps
+the entry.
+.IP \(bu 2
+An entry that cannot be removed is reported, and the directories holding it
+are kept. With \fB\-f\fR, a missing \fIFILE\fR is skipped silently, and
+failures are counted in the summary but neither reported nor reflected in the
+exit status.
+.IP \(bu 2
+A first SIGINT or SIGTERM stops the run after the entries being removed,
+lists the targets left partially deleted or untouched, and keeps the
+\fB\-\-journal\fR so that \fB\-\-resume\fR can finish the job. A second one
+exits immediately.
+.IP \(bu 2
+\fB\-\-background\fR only renames each target to a hidden
+\fI.rmx\-tombstone.*\fR entry in the same directory; the rename is atomic, and
+a worker killed midway is resumed by the next run.
+.IP \(bu 2
+rmx has no \fB\-\-preserve\-root\fR failsafe of its own: \fBrmx \-rf /\fR is
+carried out. GNU mode (\fB\-\-compat\fR=\fIgnu\fR) refuses \fI/\fR by default,
+like GNU rm.
+.SH EXIT STATUS
+.TP
+.B 0
+Every \fIFILE\fR was removed; with \fB\-f\fR, also when some could not be.
+.TP
+.B 1
+An entry could not be removed, a \fIFILE\fR does not exist, the command line
+is invalid, or a command failed.
+.TP
+.B 128+N
+Interrupted by signal \fIN\fR: 130 for SIGINT, 143 for SIGTERM.
+.SH ENVIRONMENT
+.TP
+.B NO_COLOR
+When set and not empty, disables colors unless \fB\-\-color\fR=\fIalways\fR.
+.TP
+.B CLICOLOR_FORCE
+When set and not 0, enables colors even when not writing to a terminal.
+.TP
+.B TERM
+\fIdumb\fR disables colors and status glyphs.
+.TP
+.B RMX_AUDIT_CONFIG
+Audit log configuration to read instead of \fI{config}\fR; only honored
+when the real and effective user IDs are both 0.
+.TP
+.B XDG_STATE_HOME
+Where \fB\-\-background\fR keeps its list of pending tombstones, under
+\fIrmx/\fR (default \fI~/.local/state/rmx\fR, or \fB$XDG_RUNTIME_DIR\fR\fI/rmx\fR
+without \fBHOME\fR). The directory must belong to the user running rmx.
+.SH FILES
+.TP
+.I {config}
+System\-wide audit log: when present, every run is recorded to a rotated
+file or to syslog, once before anything is removed and once when done.
+.SH EXAMPLES
+"#,
+        config = escape(audit::CONFIG_PATH)
+    ));
+    for (command, what) in commands::EXAMPLES {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", escape(command), escape(what)));
+    }
+
+    page.push_str(".SH SEE ALSO\nrm(1), find(1), unlink(2)\n");
+    print!("{}", page);
+}
+
+/// `.TP` paragraph for an option: names, then its help
+fn option_item(spec: &OptionSpec) -> String {
+    let mut names: Vec<String> = spec
+        .short
+        .iter()
+        .map(|c| format!("\\fB\\-{}\\fR", c))
+        .collect();
+    names.push(format!("\\fB{}\\fR", escape(spec.long)));
+    let value = match spec.value {
+        Value::None => String::new(),
+        Value::Required(name) => format!("=\\fI{}\\fR", escape(name)),
+        Value::Optional(name) => format!("[=\\fI{}\\fR]", escape(name)),
+    };
+    format!(
+        ".TP\n{}{}\n{}\n",
+        names.join(", "),
+        value,
+        escape(&spec.help.join(" "))
+    )
+}
+
+/// Text safe to put in a roff line
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    // A leading '.' or '\'' would start a request
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+use std::env;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use termion::{color, style};
+
+/// When to color output (`--color`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Only on a terminal, unless NO_COLOR or CLICOLOR_FORCE say otherwise
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub const ALL: [ColorChoice; 3] = [ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorChoice> {
+        Self::ALL.into_iter().find(|choice| choice.name() == name)
+    }
+}
+
+static CHOICE: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Set once from the command line; subcommands keep `auto`
+pub fn set_color(choice: ColorChoice) {
+    CHOICE.store(choice as u8, Ordering::Relaxed);
+}
+
+/// Plain ASCII output, without the ✓ glyphs (`--ascii`)
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+/// Whether glyphs are replaced by plain text; also on dumb terminals
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed) || dumb_terminal()
+}
+
+fn dumb_terminal() -> bool {
+    env::var_os("TERM").is_some_and(|term| term == "dumb")
+}
+
+fn choice() -> ColorChoice {
+    ColorChoice::ALL
+        .into_iter()
+        .find(|&choice| choice as u8 == CHOICE.load(Ordering::Relaxed))
+        .unwrap_or_default()
+}
+
+/// Stream a message is written to; each one is checked for a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Whether escape codes may be written to `stream`
+///
+/// `--color=always|never` win over the environment. Under `auto` a
+/// non-empty NO_COLOR disables colors, CLICOLOR_FORCE (other than "0")
+/// enables them even when piped, and otherwise the stream must be a
+/// terminal that is not TERM=dumb.
+pub fn colors_enabled(stream: Stream) -> bool {
+    match choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                return false;
+            }
+            if env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0") {
+                return true;
+            }
+            let tty = match stream {
+                Stream::Stdout => termion::is_tty(&io::stdout()),
+                Stream::Stderr => termion::is_tty(&io::stderr()),
+            };
+            tty && !dumb_terminal()
+        }
+    }
+}
+
+/// The few colors rmx uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// Success and totals
+    Green,
+    /// Headings and progress
+    Cyan,
+    /// Retries, skips, interruptions
+    Yellow,
+    /// Errors
+    Red,
+}
+
+impl Color {
+    /// `text` colored for stdout
+    pub fn paint<T: fmt::Display>(self, text: T) -> Paint<T> {
+        Paint {
+            color: self,
+            stream: Stream::Stdout,
+            text,
+        }
+    }
+
+    /// `text` colored for stderr
+    pub fn paint_err<T: fmt::Display>(self, text: T) -> Paint<T> {
+        Paint {
+            color: self,
+            stream: Stream::Stderr,
+            text,
+        }
+    }
+}
+
+/// Text that is wrapped in escape codes only when its stream allows it
+pub struct Paint<T> {
+    color: Color,
+    stream: Stream,
+    text: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Paint<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !colors_enabled(self.stream) {
+            return write!(f, "{}", self.text);
+        }
+        match self.color {
+            Color::Green => write!(f, "{}", color::Fg(color::LightGreen))?,
+            Color::Cyan => write!(f, "{}", color::Fg(color::LightCyan))?,
+            Color::Yellow => write!(f, "{}", color::Fg(color::LightYellow))?,
+            Color::Red => write!(f, "{}", color::Fg(color::LightRed))?,
+        }
+        write!(f, "{}{}", self.text, style::Reset)
+    }
+}
+
+/// Status glyph at the start of a summary line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Ok,
+    Retry,
+    Skip,
+    Fail,
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The words after the glyph already say it, so ASCII mode drops it
+        if ascii() {
+            return Ok(());
+        }
+        f.write_str(match self {
+            Mark::Ok => "✓ ",
+            Mark::Retry => "↻ ",
+            Mark::Skip => "- ",
+            Mark::Fail => "✗ ",
+        })
+    }
+}
+
+/// Our own text (help, about, durations) with bullets and symbols made ASCII
+pub fn plain(text: &str) -> Cow<'_, str> {
+    if !ascii() || text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace('•', "-").replace('×', "x").replace('µ', "u"))
+}
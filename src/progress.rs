@@ -0,0 +1,232 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use termion::clear;
+
+use crate::output::Color;
+use crate::util::bytes_to_readable;
+use rmx_lib::{AtomicStats, DeleteStats};
+
+const REFRESH: Duration = Duration::from_millis(100);
+const BAR_WIDTH: usize = 30;
+const SEPARATOR: &str = "  ";
+
+/// Single-line progress display redrawn by a background thread
+pub struct ProgressDisplay {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ProgressDisplay {
+    /// Whether a progress line can be drawn at all
+    pub fn available() -> bool {
+        termion::is_tty(&io::stdout())
+    }
+
+    /// Start redrawing `stats`, measured against the pre-counted `total` if any
+    pub fn start(stats: Arc<AtomicStats>, total: Option<DeleteStats>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            while !thread_stop.load(Ordering::Relaxed) {
+                render(&stats.to_delete_stats(), total.as_ref(), start.elapsed());
+                thread::sleep(REFRESH);
+            }
+        });
+
+        ProgressDisplay {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the render thread and clear the progress line
+    pub fn finish(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        print!("\r{}", clear::CurrentLine);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// A piece of the progress line
+struct Field {
+    text: String,
+    color: Option<Color>,
+    /// Fields with the lowest rank are dropped first on narrow terminals
+    rank: u8,
+}
+
+impl Field {
+    fn new(text: String, rank: u8) -> Self {
+        Field {
+            text,
+            color: None,
+            rank,
+        }
+    }
+}
+
+fn render(done: &DeleteStats, total: Option<&DeleteStats>, elapsed: Duration) {
+    let entries = done.files_deleted + done.dirs_deleted;
+    // The first moments are all burst, wait before extrapolating a rate
+    let rate = if elapsed >= Duration::from_millis(500) {
+        entries as f64 / elapsed.as_secs_f64()
+    } else {
+        0.0
+    };
+
+    let mut fields = match total {
+        Some(total) => {
+            let total_entries = (total.files_deleted + total.dirs_deleted).max(1);
+            let fraction = (entries as f64 / total_entries as f64).min(1.0);
+            let filled = (fraction * BAR_WIDTH as f64) as usize;
+            let eta = if rate > 0.0 {
+                let left = total_entries.saturating_sub(entries) as f64 / rate;
+                format_eta(Duration::from_secs_f64(left))
+            } else {
+                "--".to_string()
+            };
+
+            vec![
+                Field {
+                    color: Some(Color::Green),
+                    ..Field::new(
+                        format!("[{}{}]", "=".repeat(filled), " ".repeat(BAR_WIDTH - filled)),
+                        3,
+                    )
+                },
+                Field::new(format!("{:>3}%", (fraction * 100.0) as u32), 7),
+                Field::new(
+                    format!("{}/{} files", done.files_deleted, total.files_deleted),
+                    6,
+                ),
+                Field::new(
+                    format!("{}/{} dirs", done.dirs_deleted, total.dirs_deleted),
+                    2,
+                ),
+                Field::new(
+                    format!(
+                        "{}/{}",
+                        bytes_to_readable(done.total_size),
+                        bytes_to_readable(total.total_size)
+                    ),
+                    1,
+                ),
+                Field::new(format!("{:.0} entries/s", rate), 4),
+                Field::new(format!("ETA {}", eta), 5),
+            ]
+        }
+        None => vec![
+            Field {
+                color: Some(Color::Cyan),
+                ..Field::new("Deleting...".to_string(), 2)
+            },
+            Field::new(format!("{} files", done.files_deleted), 5),
+            Field::new(format!("{} dirs", done.dirs_deleted), 3),
+            Field::new(bytes_to_readable(done.total_size), 1),
+            Field::new(format!("{:.0} entries/s", rate), 4),
+        ],
+    };
+
+    // A line reaching the last column would wrap, and `\r` only returns to
+    // the start of the wrapped part
+    let cols = termion::terminal_size().map_or(80, |(cols, _)| cols as usize);
+    fit(&mut fields, cols.saturating_sub(1));
+
+    let mut line = String::new();
+    for field in &fields {
+        if !line.is_empty() {
+            line.push_str(SEPARATOR);
+        }
+        match field.color {
+            Some(color) => line.push_str(&color.paint(&field.text).to_string()),
+            None => line.push_str(&field.text),
+        }
+    }
+
+    print!("\r{}{}", clear::CurrentLine, line);
+    let _ = io::stdout().flush();
+}
+
+/// Drop the least useful fields until the line fits in `width` columns,
+/// then cut the last one left if need be
+fn fit(fields: &mut Vec<Field>, width: usize) {
+    let line_width = |fields: &[Field]| {
+        let text: usize = fields.iter().map(|f| f.text.chars().count()).sum();
+        text + SEPARATOR.len() * fields.len().saturating_sub(1)
+    };
+
+    while fields.len() > 1 && line_width(fields) > width {
+        let lowest = (0..fields.len())
+            .min_by_key(|&i| fields[i].rank)
+            .unwrap_or_default();
+        fields.remove(lowest);
+    }
+    if let Some(field) = fields.first_mut() {
+        if field.text.chars().count() > width {
+            field.text = field.text.chars().take(width).collect();
+        }
+    }
+}
+
+fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("[====]".to_string(), 3),
+            Field::new(" 50%".to_string(), 7),
+            Field::new("5/10 files".to_string(), 6),
+            Field::new("ETA 3s".to_string(), 5),
+        ]
+    }
+
+    fn texts(fields: &[Field]) -> Vec<&str> {
+        fields.iter().map(|f| f.text.as_str()).collect()
+    }
+
+    #[test]
+    fn wide_terminal_keeps_everything() {
+        let mut line = fields();
+        fit(&mut line, 80);
+        assert_eq!(texts(&line), ["[====]", " 50%", "5/10 files", "ETA 3s"]);
+    }
+
+    #[test]
+    fn narrow_terminal_drops_lowest_ranks_first() {
+        let mut line = fields();
+        // Everything takes 32 columns, without the bar 24
+        fit(&mut line, 31);
+        assert_eq!(texts(&line), [" 50%", "5/10 files", "ETA 3s"]);
+
+        fit(&mut line, 16);
+        assert_eq!(texts(&line), [" 50%", "5/10 files"]);
+    }
+
+    #[test]
+    fn last_field_is_cut_to_the_width() {
+        let mut line = fields();
+        fit(&mut line, 2);
+        assert_eq!(texts(&line), [" 5"]);
+    }
+}
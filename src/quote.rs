@@ -0,0 +1,235 @@
+use std::fmt::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How file names are written in messages and prompts (`--quoting-style`)
+///
+/// Every style but `Literal` is terminal safe: control characters and bytes
+/// that are not valid UTF-8 never reach the terminal as is, so a name holding
+/// a newline or an escape sequence cannot fake or garble the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuotingStyle {
+    /// `a\nb` as is, no quotes
+    Literal,
+    /// `'a?b'`, unprintable characters shown as `?`
+    Shell,
+    /// `'a'$'\n''b'`, can be pasted back into a POSIX shell
+    #[default]
+    ShellEscape,
+    /// `"a\nb"`, C string syntax
+    C,
+    /// `a\nb`, C escapes without quotes
+    Escape,
+}
+
+impl QuotingStyle {
+    pub const ALL: [QuotingStyle; 5] = [
+        QuotingStyle::Literal,
+        QuotingStyle::Shell,
+        QuotingStyle::ShellEscape,
+        QuotingStyle::C,
+        QuotingStyle::Escape,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QuotingStyle::Literal => "literal",
+            QuotingStyle::Shell => "shell",
+            QuotingStyle::ShellEscape => "shell-escape",
+            QuotingStyle::C => "c",
+            QuotingStyle::Escape => "escape",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<QuotingStyle> {
+        QuotingStyle::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// Write `path` in this style
+    pub fn quote(self, path: &Path) -> String {
+        let bytes = path.as_os_str().as_bytes();
+        match self {
+            QuotingStyle::Literal => String::from_utf8_lossy(bytes).into_owned(),
+            QuotingStyle::Shell => shell(bytes, false),
+            QuotingStyle::ShellEscape => shell(bytes, true),
+            QuotingStyle::C => format!("\"{}\"", c_escape(bytes, true)),
+            QuotingStyle::Escape => c_escape(bytes, false),
+        }
+    }
+}
+
+/// Style used by `quote` (and so by every message of rmx_lib)
+static STYLE: AtomicU8 = AtomicU8::new(QuotingStyle::ShellEscape as u8);
+
+/// Set the process-wide style for file names in messages
+pub fn set_style(style: QuotingStyle) {
+    STYLE.store(style as u8, Ordering::Relaxed);
+}
+
+pub fn style() -> QuotingStyle {
+    let current = STYLE.load(Ordering::Relaxed);
+    QuotingStyle::ALL
+        .into_iter()
+        .find(|&s| s as u8 == current)
+        .unwrap_or_default()
+}
+
+/// `path` in the current style, ready to be printed
+pub fn quote(path: &Path) -> String {
+    style().quote(path)
+}
+
+/// A file name split into what can be printed and what must be escaped
+enum Piece {
+    Plain(char),
+    Byte(u8),
+}
+
+fn pieces(bytes: &[u8]) -> Vec<Piece> {
+    let mut pieces = Vec::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for ch in chunk.valid().chars() {
+            if ch.is_control() {
+                let mut buf = [0; 4];
+                pieces.extend(ch.encode_utf8(&mut buf).bytes().map(Piece::Byte));
+            } else {
+                pieces.push(Piece::Plain(ch));
+            }
+        }
+        pieces.extend(chunk.invalid().iter().copied().map(Piece::Byte));
+    }
+    pieces
+}
+
+/// Single-quoted for a POSIX shell; unprintables as `$'\xNN'` or `?`
+fn shell(bytes: &[u8], escape_unprintable: bool) -> String {
+    let mut out = String::from("'");
+    let mut in_escape = false;
+    for piece in pieces(bytes) {
+        match piece {
+            Piece::Plain(ch) => {
+                if in_escape {
+                    out.push_str("''");
+                    in_escape = false;
+                }
+                match ch {
+                    '\'' => out.push_str("'\\''"),
+                    _ => out.push(ch),
+                }
+            }
+            Piece::Byte(_) if !escape_unprintable => out.push('?'),
+            Piece::Byte(byte) => {
+                if !in_escape {
+                    out.push_str("'$'");
+                    in_escape = true;
+                }
+                push_c_escape(&mut out, byte);
+            }
+        }
+    }
+    out.push('\'');
+    out
+}
+
+fn c_escape(bytes: &[u8], quoted: bool) -> String {
+    let mut out = String::new();
+    for piece in pieces(bytes) {
+        match piece {
+            Piece::Plain('\\') => out.push_str("\\\\"),
+            Piece::Plain('"') if quoted => out.push_str("\\\""),
+            Piece::Plain(' ') if !quoted => out.push_str("\\ "),
+            Piece::Plain(ch) => out.push(ch),
+            Piece::Byte(byte) => push_c_escape(&mut out, byte),
+        }
+    }
+    out
+}
+
+fn push_c_escape(out: &mut String, byte: u8) {
+    match byte {
+        0x07 => out.push_str("\\a"),
+        0x08 => out.push_str("\\b"),
+        b'\t' => out.push_str("\\t"),
+        b'\n' => out.push_str("\\n"),
+        0x0b => out.push_str("\\v"),
+        0x0c => out.push_str("\\f"),
+        b'\r' => out.push_str("\\r"),
+        _ => {
+            let _ = write!(out, "\\x{:02x}", byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    fn quoted(style: QuotingStyle, raw: &[u8]) -> String {
+        style.quote(Path::new(OsStr::from_bytes(raw)))
+    }
+
+    #[test]
+    fn literal_is_unchanged() {
+        assert_eq!(quoted(QuotingStyle::Literal, b"a b\nc"), "a b\nc");
+        assert_eq!(quoted(QuotingStyle::Literal, b"x\xff"), "x\u{fffd}");
+    }
+
+    #[test]
+    fn shell_quotes_and_hides_unprintables() {
+        assert_eq!(quoted(QuotingStyle::Shell, b"plain"), "'plain'");
+        assert_eq!(quoted(QuotingStyle::Shell, b"it's"), "'it'\\''s'");
+        assert_eq!(quoted(QuotingStyle::Shell, b"a\nb"), "'a?b'");
+        assert_eq!(quoted(QuotingStyle::Shell, b"a\xffb"), "'a?b'");
+    }
+
+    #[test]
+    fn shell_escape_can_be_pasted_back() {
+        assert_eq!(quoted(QuotingStyle::ShellEscape, b"plain"), "'plain'");
+        assert_eq!(quoted(QuotingStyle::ShellEscape, b"a\nb"), "'a'$'\\n''b'");
+        assert_eq!(
+            quoted(QuotingStyle::ShellEscape, b"\x1b[31m"),
+            "''$'\\x1b''[31m'"
+        );
+        assert_eq!(quoted(QuotingStyle::ShellEscape, b"a\xff"), "'a'$'\\xff'");
+        assert_eq!(quoted(QuotingStyle::ShellEscape, b"it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn c_style() {
+        assert_eq!(quoted(QuotingStyle::C, b"a b"), "\"a b\"");
+        assert_eq!(quoted(QuotingStyle::C, b"say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quoted(QuotingStyle::C, b"a\\b\tc"), "\"a\\\\b\\tc\"");
+        assert_eq!(
+            quoted(QuotingStyle::C, b"\x07\x08\x0b\x0c\r"),
+            "\"\\a\\b\\v\\f\\r\""
+        );
+        assert_eq!(quoted(QuotingStyle::C, b"\x7f\xc3"), "\"\\x7f\\xc3\"");
+    }
+
+    #[test]
+    fn escape_style_has_no_quotes() {
+        assert_eq!(quoted(QuotingStyle::Escape, b"a b\nc"), "a\\ b\\nc");
+        assert_eq!(quoted(QuotingStyle::Escape, b"\"q\""), "\"q\"");
+    }
+
+    #[test]
+    fn valid_unicode_is_kept() {
+        for style in QuotingStyle::ALL {
+            assert!(
+                quoted(style, "café ✓".as_bytes()).contains("café"),
+                "{:?}",
+                style
+            );
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for style in QuotingStyle::ALL {
+            assert_eq!(QuotingStyle::from_name(style.name()), Some(style));
+        }
+        assert_eq!(QuotingStyle::from_name("bogus"), None);
+    }
+}
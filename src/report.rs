@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use rmx_lib::{DeleteStats, RmxError};
+
+/// Whole-run summary printed by `--output=json`
+///
+/// Paths that are not valid UTF-8 are converted lossily (U+FFFD).
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    version: &'static str,
+    #[serde(flatten)]
+    totals: JsonCounts<'a>,
+    elapsed_seconds: f64,
+    errors: Vec<JsonError>,
+    targets: Vec<JsonTarget<'a>>,
+    /// Signal that stopped the run, e.g. "SIGINT"
+    interrupted: Option<&'static str>,
+    /// Targets left partially deleted or untouched by the interruption
+    incomplete: Vec<String>,
+}
+
+/// Counts shared by the whole run and each target
+#[derive(Debug, Serialize)]
+pub struct JsonCounts<'a> {
+    files: usize,
+    directories: usize,
+    symlinks: usize,
+    special: usize,
+    bytes: u64,
+    retries: usize,
+    skipped: usize,
+    failed: usize,
+    failed_by_code: &'a BTreeMap<&'static str, usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTarget<'a> {
+    path: String,
+    #[serde(flatten)]
+    counts: JsonCounts<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonError {
+    path: String,
+    code: &'static str,
+    message: String,
+}
+
+impl<'a> JsonCounts<'a> {
+    pub fn new(stats: &'a DeleteStats) -> Self {
+        JsonCounts {
+            files: stats.files_deleted,
+            directories: stats.dirs_deleted,
+            symlinks: stats.symlinks_deleted,
+            special: stats.special_deleted,
+            bytes: stats.total_size,
+            retries: stats.retries,
+            skipped: stats.skipped,
+            failed: stats.failed_count(),
+            failed_by_code: &stats.failed,
+        }
+    }
+}
+
+impl<'a> JsonTarget<'a> {
+    pub fn new(path: &Path, stats: &'a DeleteStats) -> Self {
+        JsonTarget {
+            path: lossy(path),
+            counts: JsonCounts::new(stats),
+        }
+    }
+}
+
+impl JsonError {
+    pub fn new(error: &RmxError) -> Self {
+        JsonError {
+            path: lossy(error.path()),
+            code: error.code(),
+            message: error.description().to_string(),
+        }
+    }
+}
+
+fn lossy(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Print the run as a single JSON document on stdout
+pub fn print_json(
+    total: &DeleteStats,
+    elapsed: Duration,
+    targets: &[(&PathBuf, DeleteStats)],
+    interrupted: Option<&'static str>,
+    incomplete: &[&PathBuf],
+) {
+    let report = JsonReport {
+        version: env!("CARGO_PKG_VERSION"),
+        totals: JsonCounts::new(total),
+        elapsed_seconds: elapsed.as_secs_f64(),
+        errors: total.errors.iter().map(JsonError::new).collect(),
+        targets: targets
+            .iter()
+            .map(|(path, stats)| JsonTarget::new(path, stats))
+            .collect(),
+        interrupted,
+        incomplete: incomplete.iter().map(|path| lossy(path)).collect(),
+    };
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("rmx: Failed to serialize report: {}", e),
+    }
+}
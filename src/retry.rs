@@ -0,0 +1,87 @@
+use std::io;
+use std::time::Duration;
+
+/// Retry removals that race with a process still writing into the tree
+///
+/// A directory that fails with ENOTEMPTY is re-scanned and the entries that
+/// appeared meanwhile are deleted before `rmdir` is tried again; unlinks
+/// failing with EBUSY/ETXTBSY are simply tried again. Each retry waits
+/// twice as long as the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// Attempts after the first failure
+    pub attempts: u32,
+    /// Wait before the first retry
+    pub delay: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            attempts: 3,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl Retry {
+    /// Wait before retry number `attempt` (starting at 0)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.delay.saturating_mul(1 << attempt.min(16))
+    }
+}
+
+/// The file is in use and may be released shortly
+pub fn is_busy(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::EBUSY) | Some(libc::ETXTBSY))
+}
+
+/// New entries appeared in a directory being removed
+pub fn is_not_empty(err: &io::Error) -> bool {
+    // POSIX allows EEXIST in place of ENOTEMPTY
+    matches!(
+        err.raw_os_error(),
+        Some(libc::ENOTEMPTY) | Some(libc::EEXIST)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_each_attempt() {
+        let retry = Retry::default();
+        assert_eq!(retry.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff(1), Duration::from_millis(200));
+        assert_eq!(retry.backoff(3), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn backoff_stops_growing() {
+        let retry = Retry {
+            attempts: 100,
+            delay: Duration::from_millis(1),
+        };
+        assert_eq!(retry.backoff(16), retry.backoff(40));
+        assert_eq!(retry.backoff(u32::MAX), Duration::from_millis(1 << 16));
+
+        let huge = Retry {
+            attempts: 1,
+            delay: Duration::MAX,
+        };
+        assert_eq!(huge.backoff(5), Duration::MAX);
+    }
+
+    #[test]
+    fn classifies_errors() {
+        let err = io::Error::from_raw_os_error;
+        assert!(is_busy(&err(libc::EBUSY)));
+        assert!(is_busy(&err(libc::ETXTBSY)));
+        assert!(!is_busy(&err(libc::ENOTEMPTY)));
+        assert!(is_not_empty(&err(libc::ENOTEMPTY)));
+        assert!(is_not_empty(&err(libc::EEXIST)));
+        assert!(!is_not_empty(&err(libc::EACCES)));
+        assert!(!is_not_empty(&io::Error::other("not an os error")));
+    }
+}
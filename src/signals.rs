@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::util::bytes_to_readable;
+use rmx_lib::quote;
+use rmx_lib::AtomicStats;
+
+/// Signal that interrupted the run (0 = none)
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+/// Signals that print a progress snapshot (like `dd`)
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+const SNAPSHOT_SIGNALS: [libc::c_int; 2] = [libc::SIGUSR1, libc::SIGINFO];
+#[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+const SNAPSHOT_SIGNALS: [libc::c_int; 1] = [libc::SIGUSR1];
+
+/// Route signals to a watcher thread
+///
+/// SIGINT/SIGTERM raise the returned cancellation flag (a second one exits
+/// immediately); SIGUSR1/SIGINFO print a snapshot of `live` to stderr.
+///
+/// Must run before any other thread is spawned: the signals are blocked here
+/// and every later thread (Rayon workers, progress display) inherits the mask,
+/// so only the watcher ever sees them.
+pub fn install(live: Arc<AtomicStats>) -> Arc<AtomicBool> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut signals = vec![libc::SIGINT, libc::SIGTERM];
+    signals.extend(SNAPSHOT_SIGNALS);
+    let set = signal_set(&signals);
+    let start = Instant::now();
+
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+    }
+
+    let watcher_cancel = Arc::clone(&cancel);
+    thread::spawn(move || loop {
+        let mut signal: libc::c_int = 0;
+        if unsafe { libc::sigwait(&set, &mut signal) } != 0 {
+            continue;
+        }
+
+        if SNAPSHOT_SIGNALS.contains(&signal) {
+            print_snapshot(&live, start.elapsed());
+            continue;
+        }
+
+        if watcher_cancel.load(Ordering::SeqCst) {
+            std::process::exit(exit_code(signal));
+        }
+        RECEIVED.store(signal, Ordering::SeqCst);
+        watcher_cancel.store(true, Ordering::SeqCst);
+    });
+
+    cancel
+}
+
+/// The signal that cancelled the run, if any
+pub fn received() -> Option<i32> {
+    match RECEIVED.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Conventional shell status for death by signal
+pub fn exit_code(signal: i32) -> i32 {
+    128 + signal
+}
+
+pub fn name(signal: i32) -> &'static str {
+    match signal {
+        libc::SIGINT => "SIGINT",
+        libc::SIGTERM => "SIGTERM",
+        _ => "signal",
+    }
+}
+
+fn print_snapshot(live: &AtomicStats, elapsed: Duration) {
+    let stats = live.to_delete_stats();
+    let entries = stats.files_deleted + stats.dirs_deleted;
+    let rate = if elapsed.as_secs_f64() > 0.0 {
+        entries as f64 / elapsed.as_secs_f64()
+    } else {
+        0.0
+    };
+
+    eprintln!(
+        "rmx: {} files, {} dirs, {} deleted in {:.2?} ({:.0} entries/s)",
+        stats.files_deleted,
+        stats.dirs_deleted,
+        bytes_to_readable(stats.total_size),
+        elapsed,
+        rate
+    );
+    if let Some(dir) = live.current_dir() {
+        eprintln!("rmx: in {}", quote::quote(&dir));
+    }
+}
+
+fn signal_set(signals: &[libc::c_int]) -> libc::sigset_t {
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for &signal in signals {
+            libc::sigaddset(&mut set, signal);
+        }
+        set
+    }
+}
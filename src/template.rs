@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// `find -printf` style line printed for every removed entry (`--format`)
+///
+/// | Directive | Meaning                                              |
+/// |-----------|------------------------------------------------------|
+/// | `%p`      | path, as given or found (raw bytes, not quoted)      |
+/// | `%s`      | size in bytes                                        |
+/// | `%T`      | modification time, seconds since the Unix epoch      |
+/// | `%y`      | type: `f`, `d`, `l`, `p` (FIFO), `s`, `b`, `c`        |
+/// | `%d`      | depth below the command line argument (0 for itself) |
+/// | `%u`      | owner name, or the uid if it has none                |
+/// | `%U`      | owner uid                                            |
+/// | `%%`      | a literal `%`                                        |
+///
+/// `\n`, `\t`, `\0` and `\\` are escapes. Like `find -printf`, no newline
+/// is added: end the template with `\n` (or `\0` for `xargs -0`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryTemplate {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(Vec<u8>),
+    Path,
+    Size,
+    MTime,
+    Type,
+    Depth,
+    Owner,
+    Uid,
+}
+
+impl EntryTemplate {
+    pub fn parse(template: &str) -> Result<EntryTemplate, String> {
+        let mut pieces = Vec::new();
+        let mut literal = Vec::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            let piece = match c {
+                '%' => match chars.next() {
+                    Some('%') => {
+                        literal.push(b'%');
+                        continue;
+                    }
+                    Some('p') => Piece::Path,
+                    Some('s') => Piece::Size,
+                    Some('T') => Piece::MTime,
+                    Some('y') => Piece::Type,
+                    Some('d') => Piece::Depth,
+                    Some('u') => Piece::Owner,
+                    Some('U') => Piece::Uid,
+                    Some(other) => return Err(format!("unknown directive '%{}'", other)),
+                    None => return Err("'%' at the end of the template".to_string()),
+                },
+                '\\' => {
+                    match chars.next() {
+                        Some('n') => literal.push(b'\n'),
+                        Some('t') => literal.push(b'\t'),
+                        Some('0') => literal.push(b'\0'),
+                        Some('\\') => literal.push(b'\\'),
+                        Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+                        None => literal.push(b'\\'),
+                    }
+                    continue;
+                }
+                _ => {
+                    let mut buf = [0; 4];
+                    literal.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    continue;
+                }
+            };
+            if !literal.is_empty() {
+                pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+            }
+            pieces.push(piece);
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+
+        Ok(EntryTemplate { pieces })
+    }
+
+    /// The line for `path`, using its metadata from before the removal
+    pub fn render(&self, path: &Path, metadata: &fs::Metadata, depth: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(bytes) => out.extend_from_slice(bytes),
+                Piece::Path => out.extend_from_slice(path.as_os_str().as_bytes()),
+                Piece::Size => out.extend_from_slice(metadata.len().to_string().as_bytes()),
+                Piece::MTime => out.extend_from_slice(metadata.mtime().to_string().as_bytes()),
+                Piece::Type => out.push(type_letter(metadata.file_type())),
+                Piece::Depth => out.extend_from_slice(depth.to_string().as_bytes()),
+                Piece::Owner => out.extend_from_slice(user_name(metadata.uid()).as_bytes()),
+                Piece::Uid => out.extend_from_slice(metadata.uid().to_string().as_bytes()),
+            }
+        }
+        out
+    }
+}
+
+/// Same letters as `find -printf %y`
+fn type_letter(file_type: fs::FileType) -> u8 {
+    if file_type.is_dir() {
+        b'd'
+    } else if file_type.is_symlink() {
+        b'l'
+    } else if file_type.is_fifo() {
+        b'p'
+    } else if file_type.is_socket() {
+        b's'
+    } else if file_type.is_block_device() {
+        b'b'
+    } else if file_type.is_char_device() {
+        b'c'
+    } else {
+        b'f'
+    }
+}
+
+/// User name for `uid`, or the uid itself if it has none; looked up once per uid
+pub fn user_name(uid: u32) -> String {
+    static NAMES: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Ok(names) = names.lock() {
+        if let Some(name) = names.get(&uid) {
+            return name.clone();
+        }
+    }
+    let name = lookup_user(uid).unwrap_or_else(|| uid.to_string());
+    if let Ok(mut names) = names.lock() {
+        names.insert(uid, name.clone());
+    }
+    name
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    let name = unsafe { CStr::from_ptr(pwd.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_split_the_literals() {
+        let template = EntryTemplate::parse("%y %p (%s)\\n").unwrap();
+        assert_eq!(
+            template.pieces,
+            [
+                Piece::Type,
+                Piece::Literal(b" ".to_vec()),
+                Piece::Path,
+                Piece::Literal(b" (".to_vec()),
+                Piece::Size,
+                Piece::Literal(b")\n".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_directive_is_known() {
+        let template = EntryTemplate::parse("%p%s%T%y%d%u%U").unwrap();
+        assert_eq!(
+            template.pieces,
+            [
+                Piece::Path,
+                Piece::Size,
+                Piece::MTime,
+                Piece::Type,
+                Piece::Depth,
+                Piece::Owner,
+                Piece::Uid,
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_and_percent() {
+        let template = EntryTemplate::parse("100%%\\t\\0\\\\é\\").unwrap();
+        assert_eq!(
+            template.pieces,
+            [Piece::Literal("100%\t\0\\é\\".as_bytes().to_vec())]
+        );
+        assert_eq!(EntryTemplate::parse("").unwrap().pieces, []);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            EntryTemplate::parse("%q").unwrap_err(),
+            "unknown directive '%q'"
+        );
+        assert_eq!(
+            EntryTemplate::parse("%p %").unwrap_err(),
+            "'%' at the end of the template"
+        );
+        assert_eq!(
+            EntryTemplate::parse("\\x").unwrap_err(),
+            "unknown escape '\\x'"
+        );
+    }
+
+    #[test]
+    fn render_uses_the_metadata() {
+        let dir = std::env::temp_dir().join(format!("rmx-template-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("f");
+        fs::write(&file, b"12345").unwrap();
+        let metadata = fs::symlink_metadata(&file).unwrap();
+
+        let template = EntryTemplate::parse("%y %s %d %U %p\\0").unwrap();
+        let expected = format!("f 5 2 {} {}\0", metadata.uid(), file.display());
+        assert_eq!(template.render(&file, &metadata, 2), expected.as_bytes());
+
+        let metadata = fs::symlink_metadata(&dir).unwrap();
+        let template = EntryTemplate::parse("%y").unwrap();
+        assert_eq!(template.render(&dir, &metadata, 0), b"d");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Token bucket shared by all workers
+///
+/// Callers reserve tokens up front and sleep off any debt outside the lock,
+/// so a burst from one worker delays the next caller instead of spinning.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    /// Allow `per_sec` tokens per second, with up to one second of burst
+    pub fn new(per_sec: u64) -> Self {
+        let rate = per_sec.max(1) as f64;
+        RateLimiter {
+            rate,
+            bucket: Mutex::new(Bucket {
+                tokens: rate,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take `amount` tokens, blocking until they are available
+    pub fn acquire(&self, amount: u64) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.last).as_secs_f64() * self.rate;
+            bucket.tokens = (bucket.tokens + refill).min(self.rate);
+            bucket.last = now;
+            bucket.tokens -= amount as f64;
+
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        };
+
+        thread::sleep(wait);
+    }
+}
+
+/// Drop the calling thread to idle I/O class and lowest CPU priority
+#[cfg(target_os = "linux")]
+pub fn lower_priority() {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    // Both calls act on the calling thread only when given id 0
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        );
+    }
+}
+
+/// Drop the process to the lowest CPU priority
+#[cfg(not(target_os = "linux"))]
+pub fn lower_priority() {
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(limiter: &RateLimiter) -> f64 {
+        limiter.bucket.lock().unwrap().tokens
+    }
+
+    #[test]
+    fn burst_within_capacity_does_not_block() {
+        let limiter = RateLimiter::new(1000);
+        let start = Instant::now();
+        limiter.acquire(600);
+        limiter.acquire(400);
+        assert!(start.elapsed() < Duration::from_millis(200));
+        assert!(tokens(&limiter) < 1.0);
+    }
+
+    #[test]
+    fn debt_is_slept_off() {
+        let limiter = RateLimiter::new(100);
+        limiter.acquire(100);
+        let start = Instant::now();
+        limiter.acquire(20);
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn refill_is_capped_at_one_second() {
+        let limiter = RateLimiter::new(50);
+        limiter.bucket.lock().unwrap().last -= Duration::from_secs(10);
+        limiter.acquire(0);
+        assert_eq!(tokens(&limiter), 50.0);
+    }
+
+    #[test]
+    fn zero_rate_is_clamped() {
+        let limiter = RateLimiter::new(0);
+        assert_eq!(limiter.rate, 1.0);
+        assert_eq!(tokens(&limiter), 1.0);
+    }
+}
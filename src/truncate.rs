@@ -0,0 +1,87 @@
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::throttle::RateLimiter;
+
+/// Shrink very large files step by step before they are unlinked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GradualTruncate {
+    /// Bytes cut off per `ftruncate` call
+    pub chunk: u64,
+    /// Only files larger than this are truncated first
+    pub threshold: u64,
+    /// Pause between two steps
+    pub pause: Duration,
+}
+
+impl Default for GradualTruncate {
+    fn default() -> Self {
+        GradualTruncate {
+            chunk: 256 * 1024 * 1024,
+            threshold: 1024 * 1024 * 1024,
+            pause: Duration::ZERO,
+        }
+    }
+}
+
+impl GradualTruncate {
+    /// Open `path` for truncation if it is large enough to need it
+    ///
+    /// The handle is checked to be the very file `metadata` describes, so a
+    /// path swapped in meanwhile is never truncated. Hard-linked files are
+    /// left alone: truncating them would destroy the data still reachable
+    /// through the other links.
+    pub fn open(&self, path: &Path, metadata: &fs::Metadata) -> Option<File> {
+        if !metadata.is_file() || metadata.nlink() != 1 || metadata.len() <= self.threshold {
+            return None;
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+            .open(path)
+            .ok()?;
+        let opened = file.metadata().ok()?;
+        let same = opened.is_file()
+            && opened.dev() == metadata.dev()
+            && opened.ino() == metadata.ino()
+            && opened.nlink() == 1;
+        same.then_some(file)
+    }
+
+    /// Truncate an already unlinked file in chunks, returning the bytes
+    /// freed only when it is closed
+    ///
+    /// Only called once the unlink succeeded, so a failed removal never
+    /// loses data. Any failure just leaves the rest to the close.
+    pub fn shrink(
+        &self,
+        file: &File,
+        size: u64,
+        bytes_limit: Option<&RateLimiter>,
+        cancel: Option<&AtomicBool>,
+    ) -> u64 {
+        let mut remaining = size;
+        let chunk = self.chunk.max(1);
+        // On cancellation the file is closed at once, as for any in-flight entry
+        while remaining > chunk && !cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            if let Some(limit) = bytes_limit {
+                limit.acquire(chunk);
+            }
+            if file.set_len(remaining - chunk).is_err() {
+                break;
+            }
+            remaining -= chunk;
+
+            if !self.pause.is_zero() {
+                thread::sleep(self.pause);
+            }
+        }
+
+        remaining
+    }
+}
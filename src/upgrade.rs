@@ -2,7 +2,8 @@ use serde::Deserialize;
 use std::env;
 use std::fs;
 use std::io::Write;
-use termion::{color, style};
+
+use crate::output::{Color, Mark};
 
 const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const GITHUB_API_URL: &str = "https://api.github.com/repos/ismoilovdevml/rmx/releases/latest";
@@ -42,11 +43,7 @@ fn get_target_triple() -> Result<String, String> {
 
 /// Check if a new version is available
 pub fn check_for_updates() -> Result<Option<String>, String> {
-    println!(
-        "{}Checking for updates...{}",
-        color::Fg(color::LightCyan),
-        style::Reset
-    );
+    println!("{}", Color::Cyan.paint("Checking for updates..."));
 
     let response = ureq::get(GITHUB_API_URL)
         .set("User-Agent", "rmx-updater")
@@ -61,19 +58,20 @@ pub fn check_for_updates() -> Result<Option<String>, String> {
 
     if latest_version != CURRENT_VERSION {
         println!(
-            "{}New version available: {} (current: {}){}",
-            color::Fg(color::LightGreen),
-            latest_version,
-            CURRENT_VERSION,
-            style::Reset
+            "{}",
+            Color::Green.paint(format_args!(
+                "New version available: {} (current: {})",
+                latest_version, CURRENT_VERSION
+            ))
         );
         Ok(Some(latest_version.to_string()))
     } else {
         println!(
-            "{}You are already using the latest version: {}{}",
-            color::Fg(color::LightGreen),
-            CURRENT_VERSION,
-            style::Reset
+            "{}",
+            Color::Green.paint(format_args!(
+                "You are already using the latest version: {}",
+                CURRENT_VERSION
+            ))
         );
         Ok(None)
     }
@@ -81,11 +79,7 @@ pub fn check_for_updates() -> Result<Option<String>, String> {
 
 /// Download and install the latest version
 pub fn upgrade() -> Result<(), String> {
-    println!(
-        "{}Starting upgrade process...{}",
-        color::Fg(color::LightCyan),
-        style::Reset
-    );
+    println!("{}", Color::Cyan.paint("Starting upgrade process..."));
 
     // Get latest release info
     let response = ureq::get(GITHUB_API_URL)
@@ -102,10 +96,8 @@ pub fn upgrade() -> Result<(), String> {
     // Check if already up to date
     if latest_version == CURRENT_VERSION {
         println!(
-            "{}Already up to date (v{}){}",
-            color::Fg(color::LightGreen),
-            CURRENT_VERSION,
-            style::Reset
+            "{}",
+            Color::Green.paint(format_args!("Already up to date (v{})", CURRENT_VERSION))
         );
         return Ok(());
     }
@@ -127,10 +119,8 @@ pub fn upgrade() -> Result<(), String> {
         })?;
 
     println!(
-        "{}Downloading rmx v{}...{}",
-        color::Fg(color::LightCyan),
-        latest_version,
-        style::Reset
+        "{}",
+        Color::Cyan.paint(format_args!("Downloading rmx v{}...", latest_version))
     );
 
     // Download the tarball
@@ -155,11 +145,7 @@ pub fn upgrade() -> Result<(), String> {
     file.write_all(&tarball_data)
         .map_err(|e| format!("Failed to write tarball: {}", e))?;
 
-    println!(
-        "{}Extracting...{}",
-        color::Fg(color::LightCyan),
-        style::Reset
-    );
+    println!("{}", Color::Cyan.paint("Extracting..."));
 
     // Extract tarball using tar command
     let extract_status = std::process::Command::new("tar")
@@ -194,40 +180,30 @@ pub fn upgrade() -> Result<(), String> {
             .map_err(|e| format!("Failed to set permissions: {}", e))?;
     }
 
-    println!(
-        "{}Installing...{}",
-        color::Fg(color::LightCyan),
-        style::Reset
-    );
+    println!("{}", Color::Cyan.paint("Installing..."));
 
     // Replace the current binary
     // Try to move first, if permission denied, suggest using sudo
     match fs::rename(&new_binary, &current_exe) {
         Ok(_) => {
             println!(
-                "{}✓ Successfully upgraded to v{}!{}",
-                color::Fg(color::LightGreen),
-                latest_version,
-                style::Reset
+                "{}",
+                Color::Green.paint(format_args!(
+                    "{}Successfully upgraded to v{}!",
+                    Mark::Ok,
+                    latest_version
+                ))
             );
         }
         Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
             // Save the path for manual installation
-            println!(
-                "{}Permission denied. Please run:{}",
-                color::Fg(color::LightYellow),
-                style::Reset
-            );
+            println!("{}", Color::Yellow.paint("Permission denied. Please run:"));
             println!(
                 "  sudo mv {} {}",
                 new_binary.display(),
                 current_exe.display()
             );
-            println!(
-                "\n{}Or run the upgrade with sudo:{}",
-                color::Fg(color::LightYellow),
-                style::Reset
-            );
+            println!("\n{}", Color::Yellow.paint("Or run the upgrade with sudo:"));
             println!("  sudo rmx upgrade");
             return Err("Permission denied. See instructions above.".to_string());
         }
@@ -238,9 +214,8 @@ pub fn upgrade() -> Result<(), String> {
     let _ = fs::remove_dir_all(&temp_dir);
 
     println!(
-        "{}Run 'rmx version' to verify the update{}",
-        color::Fg(color::LightCyan),
-        style::Reset
+        "{}",
+        Color::Cyan.paint("Run 'rmx version' to verify the update")
     );
 
     Ok(())
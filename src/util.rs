@@ -1,3 +1,6 @@
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+
 pub fn bytes_to_readable(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
@@ -13,3 +16,26 @@ pub fn bytes_to_readable(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+/// Parse a size like `4096`, `512K`, `64M` or `1G` (powers of 1024)
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last()? {
+        (i, 'K' | 'k') => (&value[..i], 1024),
+        (i, 'M' | 'm') => (&value[..i], 1024 * 1024),
+        (i, 'G' | 'g') => (&value[..i], 1024 * 1024 * 1024),
+        (i, 'T' | 't') => (&value[..i], 1024 * 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Exclusive advisory lock, released when the file is closed; without
+/// `blocking`, false when another process holds it
+pub fn lock(file: &File, blocking: bool) -> bool {
+    let mut op = libc::LOCK_EX;
+    if !blocking {
+        op |= libc::LOCK_NB;
+    }
+    unsafe { libc::flock(file.as_raw_fd(), op) == 0 }
+}